@@ -0,0 +1,426 @@
+use super::migration::{self, SCHEMA_VERSION};
+use super::LoadedCheckpoint;
+use crate::config::{CheckpointFormat, Config};
+use crate::creature::genome::Genome;
+use crate::creature::metabolism::Metabolism;
+use crate::creature::neural_net::NeuralNetwork;
+use crate::creature::Creature;
+use crate::simulation::rng::SimRng;
+use crate::simulation::tick::Direction;
+use crate::simulation::SimulationState;
+use crate::world::World;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+
+/// Binary checkpoint layout:
+///
+/// | bytes | content                                |
+/// |-------|----------------------------------------|
+/// | 8     | magic `EVOCKPT\0`                      |
+/// | 2     | format version (little-endian `u16`)   |
+/// | 4     | schema version (little-endian `u32`)   |
+/// | 8     | config hash (little-endian `u64`)      |
+/// | 8     | tick (little-endian `u64`)             |
+/// | 8     | population (little-endian `u64`)       |
+/// | 8     | max generation (little-endian `u64`)   |
+/// | 4     | CRC32 of the body (little-endian `u32`) |
+/// | rest  | deflate-compressed CBOR body           |
+///
+/// The body is CBOR rather than a positional encoding so bodies written with
+/// an older schema can still be read generically and migrated. The summary
+/// fields let checkpoints be listed without decompressing them. Format
+/// version 3 has no summary and version 2 has no checksum either.
+pub const MAGIC: &[u8; 8] = b"EVOCKPT\0";
+pub const FORMAT_VERSION: u16 = 4;
+const MIN_FORMAT_VERSION: u16 = 2;
+
+impl CheckpointFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CheckpointFormat::Binary => "evc",
+            CheckpointFormat::Json => "json",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "evc" => Some(CheckpointFormat::Binary),
+            "json" => Some(CheckpointFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Header fields of a binary checkpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BinaryHeader {
+    pub version: u16,
+    pub schema_version: u32,
+    pub config_hash: u64,
+    /// Absent before format version 4
+    pub summary: Option<CheckpointSummary>,
+    /// CRC32 of the compressed body, absent before format version 3
+    pub checksum: Option<u32>,
+}
+
+/// What a checkpoint contains, cheap to read without loading it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointSummary {
+    pub tick: u64,
+    pub population: u64,
+    pub max_generation: u64,
+}
+
+impl CheckpointSummary {
+    pub fn of(state: &SimulationState) -> Self {
+        Self {
+            tick: state.tick,
+            population: state.creatures.len() as u64,
+            max_generation: state.max_generation(),
+        }
+    }
+}
+
+impl BinaryHeader {
+    fn len(&self) -> usize {
+        let summary_len = if self.summary.is_some() { 3 * 8 } else { 0 };
+        let checksum_len = if self.checksum.is_some() { 4 } else { 0 };
+        MAGIC.len() + 2 + 4 + 8 + summary_len + checksum_len
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&self.schema_version.to_le_bytes());
+        bytes.extend_from_slice(&self.config_hash.to_le_bytes());
+        if let Some(summary) = &self.summary {
+            bytes.extend_from_slice(&summary.tick.to_le_bytes());
+            bytes.extend_from_slice(&summary.population.to_le_bytes());
+            bytes.extend_from_slice(&summary.max_generation.to_le_bytes());
+        }
+        if let Some(checksum) = self.checksum {
+            bytes.extend_from_slice(&checksum.to_le_bytes());
+        }
+    }
+}
+
+/// Reads little-endian header fields in order
+struct HeaderReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> HeaderReader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Box<dyn std::error::Error>> {
+        let field = self
+            .bytes
+            .get(self.position..self.position + N)
+            .ok_or("Checkpoint header is truncated")?;
+        self.position += N;
+        Ok(field.try_into()?)
+    }
+
+    fn u16(&mut self) -> Result<u16, Box<dyn std::error::Error>> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Box<dyn std::error::Error>> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, Box<dyn std::error::Error>> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+}
+
+/// Creature as stored on disk. The brain is left out because it is fully
+/// determined by the genome and rebuilt on load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredCreature {
+    id: u64,
+    x: usize,
+    y: usize,
+    genome: Genome,
+    metabolism: Metabolism,
+    last_reproduce_tick: u64,
+    age: u64,
+    offspring_count: u32,
+    last_damage_taken: f64,
+}
+
+impl From<&Creature> for StoredCreature {
+    fn from(creature: &Creature) -> Self {
+        Self {
+            id: creature.id,
+            x: creature.x,
+            y: creature.y,
+            genome: creature.genome.clone(),
+            metabolism: creature.metabolism.clone(),
+            last_reproduce_tick: creature.last_reproduce_tick,
+            age: creature.age,
+            offspring_count: creature.offspring_count,
+            last_damage_taken: creature.last_damage_taken,
+        }
+    }
+}
+
+impl StoredCreature {
+    fn into_creature(self, nn_config: (usize, usize, usize)) -> Creature {
+        let brain = NeuralNetwork::from_genome(&self.genome, nn_config.0, nn_config.1, nn_config.2);
+        Creature {
+            id: self.id,
+            x: self.x,
+            y: self.y,
+            genome: self.genome,
+            brain,
+            metabolism: self.metabolism,
+            last_reproduce_tick: self.last_reproduce_tick,
+            age: self.age,
+            offspring_count: self.offspring_count,
+            last_damage_taken: self.last_damage_taken,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CheckpointBody {
+    /// Network dimensions (inputs, hidden, outputs) used to rebuild brains
+    nn_config: (usize, usize, usize),
+    world: World,
+    creatures: Vec<StoredCreature>,
+    /// Attacks made in the tick before the checkpoint, sorted by attacker id
+    #[serde(default)]
+    attacks_last_tick: Vec<(u64, Vec<Direction>)>,
+    /// Extinction failsafe buffer, oldest first
+    #[serde(default)]
+    recently_dead: Vec<StoredCreature>,
+    seed: u64,
+    rng: SimRng,
+    /// Config the checkpoint was written with
+    #[serde(default)]
+    config: Option<serde_json::Value>,
+    tick: u64,
+    next_creature_id: u64,
+    total_births: u64,
+    total_deaths: u64,
+}
+
+/// Stable 64-bit FNV-1a hash of the config's JSON form, used to spot
+/// checkpoints written under a different config.
+pub fn config_hash(config: &Config) -> u64 {
+    let json = serde_json::to_vec(config).unwrap_or_default();
+    json.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub fn encode_binary(state: &SimulationState, config: &Config) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let nn_config = state
+        .creatures
+        .values()
+        .next()
+        .map(|c| c.brain.dimensions())
+        .unwrap_or((
+            config.evolution.neural_net_inputs,
+            config.evolution.neural_net_hidden,
+            config.evolution.neural_net_outputs,
+        ));
+
+    let mut creatures: Vec<StoredCreature> = state.creatures.values().map(StoredCreature::from).collect();
+    creatures.sort_unstable_by_key(|c| c.id);
+
+    let mut attacks_last_tick: Vec<(u64, Vec<Direction>)> = state
+        .attacks_last_tick
+        .iter()
+        .map(|(id, directions)| (*id, directions.clone()))
+        .collect();
+    attacks_last_tick.sort_unstable_by_key(|(id, _)| *id);
+
+    let body = CheckpointBody {
+        nn_config,
+        world: state.world.clone(),
+        creatures,
+        attacks_last_tick,
+        recently_dead: state.recently_dead.iter().map(StoredCreature::from).collect(),
+        seed: state.seed,
+        rng: state.rng.clone(),
+        config: Some(serde_json::to_value(config)?),
+        tick: state.tick,
+        next_creature_id: state.next_creature_id,
+        total_births: state.total_births,
+        total_deaths: state.total_deaths,
+    };
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    ciborium::into_writer(&body, &mut encoder)?;
+    let compressed = encoder.finish()?;
+
+    let header = BinaryHeader {
+        version: FORMAT_VERSION,
+        schema_version: SCHEMA_VERSION,
+        config_hash: config_hash(config),
+        summary: Some(CheckpointSummary::of(state)),
+        checksum: Some(crc32fast::hash(&compressed)),
+    };
+
+    let mut bytes = Vec::with_capacity(header.len() + compressed.len());
+    header.write(&mut bytes);
+    bytes.extend_from_slice(&compressed);
+    Ok(bytes)
+}
+
+pub fn read_binary_header(bytes: &[u8]) -> Result<BinaryHeader, Box<dyn std::error::Error>> {
+    if !is_binary(bytes) {
+        return Err("Not a binary checkpoint".into());
+    }
+
+    let mut reader = HeaderReader {
+        bytes,
+        position: MAGIC.len(),
+    };
+    let version = reader.u16()?;
+    if !(MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
+        return Err(format!("Unsupported checkpoint format version {}", version).into());
+    }
+
+    let schema_version = reader.u32()?;
+    let config_hash = reader.u64()?;
+    let summary = if version >= 4 {
+        Some(CheckpointSummary {
+            tick: reader.u64()?,
+            population: reader.u64()?,
+            max_generation: reader.u64()?,
+        })
+    } else {
+        None
+    };
+    let checksum = if version >= 3 { Some(reader.u32()?) } else { None };
+
+    Ok(BinaryHeader {
+        version,
+        schema_version,
+        config_hash,
+        summary,
+        checksum,
+    })
+}
+
+pub fn decode_binary(bytes: &[u8]) -> Result<LoadedCheckpoint, Box<dyn std::error::Error>> {
+    let header = read_binary_header(bytes)?;
+    let compressed = &bytes[header.len()..];
+    if let Some(expected) = header.checksum {
+        let actual = crc32fast::hash(compressed);
+        if actual != expected {
+            return Err(format!(
+                "Checkpoint checksum mismatch (expected {:08x}, found {:08x}); the file is corrupt or truncated",
+                expected, actual
+            )
+            .into());
+        }
+    }
+
+    let mut body_bytes = Vec::new();
+    DeflateDecoder::new(compressed).read_to_end(&mut body_bytes)?;
+    let body: CheckpointBody = if header.schema_version == SCHEMA_VERSION {
+        ciborium::from_reader(body_bytes.as_slice())?
+    } else {
+        let mut value: serde_json::Value = ciborium::from_reader(body_bytes.as_slice())?;
+        migration::migrate(&mut value, header.schema_version)?;
+        serde_json::from_value(value)?
+    };
+
+    let creatures: HashMap<u64, Creature> = body
+        .creatures
+        .into_iter()
+        .map(|stored| (stored.id, stored.into_creature(body.nn_config)))
+        .collect();
+
+    let mut state = SimulationState {
+        world: body.world,
+        creatures,
+        creature_positions: Default::default(),
+        attacks_last_tick: body.attacks_last_tick.into_iter().collect(),
+        recently_dead: body
+            .recently_dead
+            .into_iter()
+            .map(|stored| stored.into_creature(body.nn_config))
+            .collect::<VecDeque<_>>(),
+        seed: body.seed,
+        rng: body.rng,
+        events: Default::default(),
+        scheduled_values: Default::default(),
+        tick: body.tick,
+        next_creature_id: body.next_creature_id,
+        total_births: body.total_births,
+        total_deaths: body.total_deaths,
+    };
+    state.rebuild_spatial_index();
+
+    Ok(LoadedCheckpoint {
+        state,
+        header: Some(header),
+        config: body.config,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_round_trip_rebuilds_brains() {
+        let mut config = Config::default();
+        config.world.width = 50;
+        config.world.height = 50;
+        config.simulation.seed = Some(5);
+        let mut state = SimulationState::new(&config);
+        state.tick(&config);
+
+        let bytes = encode_binary(&state, &config).unwrap();
+        assert!(is_binary(&bytes));
+        let json_len = serde_json::to_vec(&state).unwrap().len();
+        assert!(bytes.len() * 4 < json_len);
+
+        let LoadedCheckpoint { state: loaded, header, .. } = decode_binary(&bytes).unwrap();
+        let header = header.unwrap();
+        assert_eq!(header.version, FORMAT_VERSION);
+        assert_eq!(header.schema_version, SCHEMA_VERSION);
+        assert_eq!(header.config_hash, config_hash(&config));
+        assert_eq!(header.summary, Some(CheckpointSummary::of(&state)));
+        assert_eq!(read_binary_header(&bytes[..header.len()]).unwrap(), header);
+        assert_eq!(loaded.tick, state.tick);
+        assert_eq!(loaded.creatures.len(), state.creatures.len());
+
+        for (id, creature) in &state.creatures {
+            let restored = &loaded.creatures[id];
+            assert_eq!(
+                serde_json::to_value(&restored.brain).unwrap(),
+                serde_json::to_value(&creature.brain).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_detects_truncation_and_corruption() {
+        let mut config = Config::default();
+        config.world.width = 20;
+        config.world.height = 20;
+        let state = SimulationState::new(&config);
+        let bytes = encode_binary(&state, &config).unwrap();
+
+        assert!(decode_binary(&bytes[..bytes.len() - 10]).is_err());
+
+        let mut corrupted = bytes.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xff;
+        assert!(decode_binary(&corrupted).is_err());
+    }
+}
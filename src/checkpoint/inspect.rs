@@ -0,0 +1,212 @@
+use super::LoadedCheckpoint;
+use crate::creature::Creature;
+use crate::stats::SimulationMetrics;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Offline summary of a checkpoint, for triage without starting a simulation
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectReport {
+    pub format_version: Option<u16>,
+    pub schema_version: Option<u32>,
+    pub seed: u64,
+    pub metrics: SimulationMetrics,
+    /// Number of living creatures per generation
+    pub generation_distribution: BTreeMap<u64, usize>,
+    pub oldest_creatures: Vec<CreatureSummary>,
+    pub most_prolific_parents: Vec<CreatureSummary>,
+    /// Config the checkpoint was written with, when it was stored
+    pub config: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatureSummary {
+    pub id: u64,
+    pub generation: u64,
+    pub age: u64,
+    pub offspring_count: u32,
+    pub energy: f64,
+    pub health: f64,
+    pub x: usize,
+    pub y: usize,
+}
+
+impl From<&Creature> for CreatureSummary {
+    fn from(creature: &Creature) -> Self {
+        Self {
+            id: creature.id,
+            generation: creature.genome.generation,
+            age: creature.age,
+            offspring_count: creature.offspring_count,
+            energy: creature.energy(),
+            health: creature.metabolism.health(),
+            x: creature.x,
+            y: creature.y,
+        }
+    }
+}
+
+impl InspectReport {
+    /// Build a report listing the top `limit` creatures in each ranking
+    pub fn new(checkpoint: &LoadedCheckpoint, limit: usize) -> Self {
+        let state = &checkpoint.state;
+
+        let mut generation_distribution = BTreeMap::new();
+        for creature in state.creatures.values() {
+            *generation_distribution.entry(creature.genome.generation).or_insert(0) += 1;
+        }
+
+        // Ties are broken by id so reports are stable
+        let mut creatures: Vec<&Creature> = state.creatures.values().collect();
+        creatures.sort_by_key(|c| (std::cmp::Reverse(c.age), c.id));
+        let oldest_creatures = creatures.iter().take(limit).map(|c| CreatureSummary::from(*c)).collect();
+
+        creatures.sort_by_key(|c| (std::cmp::Reverse(c.offspring_count), c.id));
+        let most_prolific_parents = creatures
+            .iter()
+            .filter(|c| c.offspring_count > 0)
+            .take(limit)
+            .map(|c| CreatureSummary::from(*c))
+            .collect();
+
+        Self {
+            format_version: checkpoint.header.map(|h| h.version),
+            schema_version: checkpoint.header.map(|h| h.schema_version),
+            seed: state.seed,
+            metrics: state.metrics(),
+            generation_distribution,
+            oldest_creatures,
+            most_prolific_parents,
+            config: checkpoint.config.clone(),
+        }
+    }
+}
+
+fn write_creatures(f: &mut fmt::Formatter<'_>, creatures: &[CreatureSummary]) -> fmt::Result {
+    if creatures.is_empty() {
+        return writeln!(f, "  (none)");
+    }
+
+    writeln!(
+        f,
+        "  {:>8} {:>5} {:>8} {:>9} {:>8} {:>8} {:>9}",
+        "id", "gen", "age", "offspring", "energy", "health", "position"
+    )?;
+    for c in creatures {
+        writeln!(
+            f,
+            "  {:>8} {:>5} {:>8} {:>9} {:>8.1} {:>8.1} {:>9}",
+            c.id,
+            c.generation,
+            c.age,
+            c.offspring_count,
+            c.energy,
+            c.health,
+            format!("{},{}", c.x, c.y)
+        )?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for InspectReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = &self.metrics;
+        match self.format_version {
+            Some(version) => writeln!(
+                f,
+                "Format: binary v{} (schema {})",
+                version,
+                self.schema_version.unwrap_or_default()
+            )?,
+            None => writeln!(f, "Format: json")?,
+        }
+        writeln!(f, "Seed: {}", self.seed)?;
+        writeln!(f)?;
+
+        writeln!(f, "Metrics")?;
+        writeln!(f, "  Tick:           {}", m.tick)?;
+        writeln!(f, "  Population:     {}", m.population)?;
+        writeln!(f, "  Avg energy:     {:.2}", m.avg_energy)?;
+        writeln!(f, "  Avg age:        {:.1}", m.avg_age)?;
+        writeln!(
+            f,
+            "  Diet:           {} herbivores, {} omnivores, {} carnivores (avg digestion {:.2})",
+            m.herbivores, m.omnivores, m.carnivores, m.avg_digestion
+        )?;
+        writeln!(f, "  Avg generation: {:.2}", m.avg_generation)?;
+        writeln!(f, "  Max generation: {}", m.max_generation)?;
+        writeln!(f, "  Total food:     {}", m.total_food)?;
+        writeln!(f, "  Total births:   {}", m.total_births)?;
+        writeln!(f, "  Total deaths:   {}", m.total_deaths)?;
+        writeln!(f)?;
+
+        writeln!(f, "Generation distribution")?;
+        let largest = self.generation_distribution.values().copied().max().unwrap_or(0);
+        for (generation, count) in &self.generation_distribution {
+            let bar = "#".repeat((count * 40).div_ceil(largest.max(1)));
+            writeln!(f, "  {:>5} {:>7} {}", generation, count, bar)?;
+        }
+        if self.generation_distribution.is_empty() {
+            writeln!(f, "  (no creatures)")?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Oldest creatures")?;
+        write_creatures(f, &self.oldest_creatures)?;
+        writeln!(f)?;
+
+        writeln!(f, "Most prolific parents")?;
+        write_creatures(f, &self.most_prolific_parents)?;
+        writeln!(f)?;
+
+        writeln!(f, "Config")?;
+        match &self.config {
+            Some(config) => {
+                let pretty = serde_json::to_string_pretty(config).map_err(|_| fmt::Error)?;
+                for line in pretty.lines() {
+                    writeln!(f, "  {}", line)?;
+                }
+            }
+            None => writeln!(f, "  (not stored in this checkpoint)")?,
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::{decode_checkpoint, encode_checkpoint};
+    use crate::config::{CheckpointFormat, Config};
+    use crate::simulation::SimulationState;
+
+    #[test]
+    fn test_report_ranks_creatures() {
+        let mut config = Config::default();
+        config.world.width = 30;
+        config.world.height = 30;
+        config.creature.initial_population = 20;
+        let mut state = SimulationState::new(&config);
+
+        let mut ids: Vec<u64> = state.creatures.keys().copied().collect();
+        ids.sort_unstable();
+        state.creatures.get_mut(&ids[3]).unwrap().age = 500;
+        state.creatures.get_mut(&ids[5]).unwrap().offspring_count = 9;
+        state.creatures.get_mut(&ids[6]).unwrap().genome.generation = 4;
+
+        let bytes = encode_checkpoint(&state, &config, CheckpointFormat::Binary).unwrap();
+        let report = InspectReport::new(&decode_checkpoint(&bytes).unwrap(), 3);
+
+        assert_eq!(report.metrics.population, 20);
+        assert_eq!(report.generation_distribution.get(&0), Some(&19));
+        assert_eq!(report.generation_distribution.get(&4), Some(&1));
+        assert_eq!(report.oldest_creatures.len(), 3);
+        assert_eq!(report.oldest_creatures[0].id, ids[3]);
+        assert_eq!(report.most_prolific_parents.len(), 1);
+        assert_eq!(report.most_prolific_parents[0].id, ids[5]);
+        assert!(report.config.is_some());
+        assert!(report.to_string().contains("Most prolific parents"));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::simulation::rng;
+use serde_json::{json, Map, Value};
+
+/// Version of the checkpointed state layout. Bump it whenever a serialized
+/// struct changes shape and add the matching entry to `MIGRATIONS`.
+///
+/// Checkpoints without a version predate versioning and are schema 0.
+pub const SCHEMA_VERSION: u32 = 2;
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// `MIGRATIONS[n]` upgrades a checkpoint from schema `n` to `n + 1`
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [v0_to_v1, v1_to_v2];
+
+/// Upgrade a checkpoint value written with `from_version` to the current schema.
+///
+/// Migrations work on the generic value so they can read layouts that no
+/// longer deserialize into the current structs. They apply to both the JSON
+/// state and the binary checkpoint body.
+pub fn migrate(state: &mut Value, from_version: u32) -> Result<(), Box<dyn std::error::Error>> {
+    if from_version > SCHEMA_VERSION {
+        return Err(format!(
+            "Checkpoint schema version {} is newer than the supported version {}",
+            from_version, SCHEMA_VERSION
+        )
+        .into());
+    }
+
+    for version in from_version..SCHEMA_VERSION {
+        log::info!("Migrating checkpoint from schema {} to {}", version, version + 1);
+        MIGRATIONS[version as usize](state)
+            .map_err(|e| format!("Checkpoint migration {} -> {} failed: {}", version, version + 1, e))?;
+    }
+
+    Ok(())
+}
+
+/// Creatures in either layout: a map keyed by id (JSON state) or a list
+/// (binary body), plus the recently dead buffer when present.
+fn creatures_mut(state: &mut Value) -> Vec<&mut Map<String, Value>> {
+    let mut creatures = Vec::new();
+    let Some(state) = state.as_object_mut() else {
+        return creatures;
+    };
+
+    for (key, value) in state.iter_mut() {
+        if key != "creatures" && key != "recently_dead" {
+            continue;
+        }
+        match value {
+            Value::Object(map) => creatures.extend(map.values_mut().filter_map(Value::as_object_mut)),
+            Value::Array(list) => creatures.extend(list.iter_mut().filter_map(Value::as_object_mut)),
+            _ => {}
+        }
+    }
+
+    creatures
+}
+
+fn cells_mut(state: &mut Value) -> Option<&mut Vec<Value>> {
+    state.get_mut("world")?.get_mut("grid")?.as_array_mut()
+}
+
+fn insert_default(object: &mut Map<String, Value>, key: &str, value: Value) {
+    object.entry(key).or_insert(value);
+}
+
+/// Fill in fields that older, unversioned checkpoints may lack
+fn v0_to_v1(state: &mut Value) -> Result<(), String> {
+    for creature in creatures_mut(state) {
+        insert_default(creature, "last_reproduce_tick", json!(0));
+        insert_default(creature, "age", json!(0));
+        insert_default(creature, "offspring_count", json!(0));
+        insert_default(creature, "last_damage_taken", json!(0.0));
+
+        if let Some(metabolism) = creature.get_mut("metabolism").and_then(Value::as_object_mut) {
+            // Health was added with combat; creatures start at full health
+            insert_default(metabolism, "max_health", json!(100.0));
+            let max_health = metabolism["max_health"].clone();
+            insert_default(metabolism, "health", max_health);
+        }
+    }
+
+    if let Some(cells) = cells_mut(state) {
+        for cell in cells.iter_mut() {
+            let Some(food) = cell.get_mut("Food") else {
+                continue;
+            };
+            // Food used to be a bare amount before meat and decay existed
+            if let Some(amount) = food.as_u64() {
+                *food = json!({ "amount": amount, "is_meat": false, "age": 0 });
+            } else if let Some(food) = food.as_object_mut() {
+                insert_default(food, "is_meat", json!(false));
+                insert_default(food, "age", json!(0));
+            } else {
+                return Err(format!("unrecognized food cell {}", food));
+            }
+        }
+    }
+
+    let object = state.as_object_mut().ok_or("checkpoint is not an object")?;
+    let next_creature_id = object
+        .get("creatures")
+        .and_then(Value::as_object)
+        .and_then(|creatures| creatures.keys().filter_map(|id| id.parse::<u64>().ok()).max())
+        .map_or(0, |max_id| max_id + 1);
+    insert_default(object, "next_creature_id", json!(next_creature_id));
+    insert_default(object, "total_births", json!(0));
+    insert_default(object, "total_deaths", json!(0));
+    insert_default(object, "seed", json!(0));
+
+    Ok(())
+}
+
+/// Checkpoints started storing the RNG state. Older ones get the RNG a
+/// resume used to derive from the seed and tick, so they load as before.
+fn v1_to_v2(state: &mut Value) -> Result<(), String> {
+    let object = state.as_object_mut().ok_or("checkpoint is not an object")?;
+    let seed = object.get("seed").and_then(Value::as_u64).ok_or("checkpoint has no seed")?;
+    let tick = object.get("tick").and_then(Value::as_u64).ok_or("checkpoint has no tick")?;
+
+    let resumed = serde_json::to_value(rng::resume_rng(seed, tick)).map_err(|e| e.to_string())?;
+    insert_default(object, "rng", resumed);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::simulation::SimulationState;
+
+    #[test]
+    fn test_migrates_unversioned_layout() {
+        let mut config = Config::default();
+        config.world.width = 20;
+        config.world.height = 20;
+        config.creature.initial_population = 5;
+        let state = SimulationState::new(&config);
+
+        // Strip the state back to an older layout
+        let mut value = serde_json::to_value(&state).unwrap();
+        value.as_object_mut().unwrap().remove("seed");
+        value.as_object_mut().unwrap().remove("next_creature_id");
+        value.as_object_mut().unwrap().remove("rng");
+        value.as_object_mut().unwrap().remove("attacks_last_tick");
+        value.as_object_mut().unwrap().remove("recently_dead");
+        for creature in value["creatures"].as_object_mut().unwrap().values_mut() {
+            let creature = creature.as_object_mut().unwrap();
+            creature.remove("offspring_count");
+            creature.remove("last_damage_taken");
+            let metabolism = creature["metabolism"].as_object_mut().unwrap();
+            metabolism.remove("health");
+            metabolism.remove("max_health");
+        }
+        value["world"]["grid"][0] = json!({ "Food": 4 });
+        value["world"]["grid"][1] = json!({ "Food": { "amount": 2 } });
+
+        assert!(serde_json::from_value::<SimulationState>(value.clone()).is_err());
+
+        migrate(&mut value, 0).unwrap();
+        let migrated: SimulationState = serde_json::from_value(value).unwrap();
+
+        assert_eq!(migrated.creatures.len(), state.creatures.len());
+        assert_eq!(migrated.next_creature_id, state.next_creature_id);
+        assert_eq!(migrated.world.get(0, 0).unwrap().food_amount(), 4);
+        assert_eq!(migrated.world.get(1, 0).unwrap().food_amount(), 2);
+        for creature in migrated.creatures.values() {
+            assert_eq!(creature.metabolism.health(), 100.0);
+        }
+    }
+
+    #[test]
+    fn test_rejects_newer_schema() {
+        let mut value = json!({});
+        assert!(migrate(&mut value, SCHEMA_VERSION + 1).is_err());
+    }
+}
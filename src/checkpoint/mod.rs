@@ -1,63 +1,183 @@
+pub mod format;
+pub mod inspect;
+pub mod migration;
+pub mod schedule;
 pub mod storage;
 
-use crate::config::Config;
+use crate::config::{CheckpointFormat, Config};
 use crate::simulation::SimulationState;
-use std::fs;
+use format::CheckpointSummary;
+use migration::SCHEMA_VERSION;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::Read;
 use std::path::Path;
 
+/// Enough leading bytes to hold any binary checkpoint header
+const HEADER_PEEK_LEN: u64 = 64;
+
+/// JSON checkpoint: the simulation state with its schema version and the
+/// config it was written with alongside
+#[derive(Serialize)]
+struct VersionedState<'a> {
+    schema_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config: Option<&'a Value>,
+    #[serde(flatten)]
+    state: &'a SimulationState,
+}
+
+#[derive(Deserialize)]
+struct SchemaVersion {
+    #[serde(default)]
+    schema_version: u32,
+    #[serde(default)]
+    config: Option<Value>,
+}
+
+/// A decoded checkpoint file
+#[derive(Debug, Clone)]
+pub struct LoadedCheckpoint {
+    pub state: SimulationState,
+    /// Header of a binary checkpoint
+    pub header: Option<format::BinaryHeader>,
+    /// Config the checkpoint was written with, as JSON. Checkpoints written
+    /// before configs were stored don't have one.
+    pub config: Option<Value>,
+}
+
 pub fn save_checkpoint(state: &SimulationState, config: &Config) -> Result<String, Box<dyn std::error::Error>> {
     let dir = Path::new(&config.checkpoint.directory);
     if !dir.exists() {
         fs::create_dir_all(dir)?;
     }
 
-    let checkpoint_path = storage::create_checkpoint_path(&config.checkpoint.directory);
-    let json = serde_json::to_string_pretty(state)?;
-    fs::write(&checkpoint_path, json)?;
+    let checkpoint_path =
+        storage::create_checkpoint_path(&config.checkpoint.directory, state.tick, config.checkpoint.format);
+    let bytes = encode_checkpoint(state, config, config.checkpoint.format)?;
+    storage::write_atomic(&checkpoint_path, &bytes)?;
 
-    storage::cleanup_old_checkpoints(&config.checkpoint.directory, config.checkpoint.keep_last_n)?;
+    storage::cleanup_old_checkpoints(
+        &config.checkpoint.directory,
+        config.checkpoint.keep_last_n,
+        config.checkpoint.retention.as_ref(),
+    )?;
 
     Ok(checkpoint_path.to_string_lossy().to_string())
 }
 
+pub fn encode_checkpoint(
+    state: &SimulationState,
+    config: &Config,
+    format: CheckpointFormat,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match format {
+        CheckpointFormat::Binary => format::encode_binary(state, config),
+        CheckpointFormat::Json => encode_json(state, Some(&serde_json::to_value(config)?)),
+    }
+}
+
+fn encode_json(state: &SimulationState, config: Option<&Value>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(serde_json::to_vec_pretty(&VersionedState {
+        schema_version: SCHEMA_VERSION,
+        config,
+        state,
+    })?)
+}
+
+/// Write a checkpoint as pretty-printed JSON, regardless of its original format
+pub fn export_json(checkpoint: &LoadedCheckpoint, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    storage::write_atomic(path, &encode_json(&checkpoint.state, checkpoint.config.as_ref())?)
+}
+
+/// Load the most recent valid checkpoint, upgrading older layouts as needed.
+///
+/// A corrupt or truncated checkpoint is skipped in favour of the one before
+/// it. If checkpoints exist but none of them load, that is an error rather
+/// than a reason to start over, so evolved populations are never silently
+/// discarded.
 pub fn load_checkpoint(config: &Config) -> Result<Option<SimulationState>, Box<dyn std::error::Error>> {
-    if let Some(checkpoint_path) = storage::find_latest_checkpoint(&config.checkpoint.directory) {
-        log::info!("Loading checkpoint from: {:?}", checkpoint_path);
-
-        match fs::read_to_string(&checkpoint_path) {
-            Ok(content) => {
-                match serde_json::from_str::<SimulationState>(&content) {
-                    Ok(mut state) => {
-                        // Rebuild spatial index since it's not serialized
-                        state.rebuild_spatial_index();
-                        Ok(Some(state))
-                    }
-                    Err(e) => {
-                        // Deserialization error - backup the old checkpoint and start fresh
-                        log::error!("Failed to parse checkpoint file: {}. Creating backup and starting fresh.", e);
-
-                        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-                        let backup_path = format!("{}.backup.{}", checkpoint_path.display(), timestamp);
-
-                        if let Err(rename_err) = fs::rename(&checkpoint_path, &backup_path) {
-                            log::error!("Failed to backup old checkpoint: {}", rename_err);
-                        } else {
-                            log::info!("Backed up old checkpoint to: {}", backup_path);
-                        }
-
-                        Ok(None)
-                    }
-                }
-            }
+    let checkpoints = storage::list_checkpoints(&config.checkpoint.directory);
+    if checkpoints.is_empty() {
+        log::info!("No checkpoint found");
+        return Ok(None);
+    }
+
+    for checkpoint in &checkpoints {
+        log::info!("Loading checkpoint from: {:?}", checkpoint.path);
+        let LoadedCheckpoint { state, header, .. } = match read_checkpoint(&checkpoint.path) {
+            Ok(loaded) => loaded,
             Err(e) => {
-                log::error!("Failed to read checkpoint file: {}", e);
-                Ok(None)
+                log::error!("Failed to load checkpoint {}: {}", checkpoint.path.display(), e);
+                continue;
+            }
+        };
+
+        if let Some(header) = header {
+            if header.config_hash != format::config_hash(config) {
+                log::warn!("Checkpoint was written with a different config than the current one");
             }
         }
-    } else {
-        log::info!("No checkpoint found");
-        Ok(None)
+
+        return Ok(Some(state));
     }
+
+    Err(format!(
+        "None of the {} checkpoints in {} could be loaded",
+        checkpoints.len(),
+        config.checkpoint.directory
+    )
+    .into())
+}
+
+/// Read and decode a specific checkpoint file
+pub fn read_checkpoint(path: &Path) -> Result<LoadedCheckpoint, Box<dyn std::error::Error>> {
+    let content = fs::read(path)?;
+    decode_checkpoint(&content)
+}
+
+/// Load a specific checkpoint file, failing instead of starting fresh
+pub fn load_checkpoint_from(path: &Path) -> Result<SimulationState, Box<dyn std::error::Error>> {
+    Ok(read_checkpoint(path)?.state)
+}
+
+/// Tick, population and max generation of a checkpoint file. Current binary
+/// checkpoints answer from their header; anything else is fully decoded.
+pub fn read_summary(path: &Path) -> Result<CheckpointSummary, Box<dyn std::error::Error>> {
+    let mut start = Vec::new();
+    File::open(path)?.take(HEADER_PEEK_LEN).read_to_end(&mut start)?;
+    if format::is_binary(&start) {
+        if let Some(summary) = format::read_binary_header(&start)?.summary {
+            return Ok(summary);
+        }
+    }
+
+    Ok(CheckpointSummary::of(&load_checkpoint_from(path)?))
+}
+
+/// Decode either checkpoint format, detected from the leading magic bytes
+pub fn decode_checkpoint(content: &[u8]) -> Result<LoadedCheckpoint, Box<dyn std::error::Error>> {
+    if format::is_binary(content) {
+        return format::decode_binary(content);
+    }
+
+    let SchemaVersion { schema_version, config } = serde_json::from_slice(content)?;
+    let mut state: SimulationState = if schema_version == SCHEMA_VERSION {
+        serde_json::from_slice(content)?
+    } else {
+        let mut value: serde_json::Value = serde_json::from_slice(content)?;
+        migration::migrate(&mut value, schema_version)?;
+        serde_json::from_value(value)?
+    };
+
+    // Rebuild spatial index since it's not serialized
+    state.rebuild_spatial_index();
+    Ok(LoadedCheckpoint {
+        state,
+        header: None,
+        config,
+    })
 }
 
 #[cfg(test)]
@@ -70,8 +190,13 @@ mod tests {
             checkpoint: crate::config::CheckpointConfig {
                 enabled: true,
                 interval_seconds: 3600,
+                interval_ticks: None,
+                interval_generations: None,
                 directory: "test_checkpoints_temp".to_string(),
                 keep_last_n: 5,
+                retention: None,
+                journal_enabled: false,
+                format: CheckpointFormat::Binary,
             },
             ..Config::default()
         };
@@ -90,4 +215,126 @@ mod tests {
 
         let _ = fs::remove_dir_all("test_checkpoints_temp");
     }
+
+    #[test]
+    fn test_json_checkpoint_still_loads() {
+        let config = Config {
+            checkpoint: crate::config::CheckpointConfig {
+                directory: "test_checkpoints_json_temp".to_string(),
+                format: CheckpointFormat::Json,
+                ..Config::default().checkpoint
+            },
+            ..Config::default()
+        };
+
+        let state = SimulationState::new(&config);
+        let path = save_checkpoint(&state, &config).unwrap();
+        assert!(path.ends_with(".json"));
+
+        let loaded = load_checkpoint(&config).unwrap().unwrap();
+        assert_eq!(loaded.creatures.len(), state.creatures.len());
+
+        let _ = fs::remove_dir_all("test_checkpoints_json_temp");
+    }
+
+    #[test]
+    fn test_resume_continues_exactly() {
+        let mut config = Config::default();
+        config.world.width = 40;
+        config.world.height = 40;
+        config.creature.initial_population = 80;
+        config.simulation.seed = Some(11);
+
+        let mut state = SimulationState::new(&config);
+        for _ in 0..30 {
+            state.tick(&config);
+        }
+
+        for format in [CheckpointFormat::Binary, CheckpointFormat::Json] {
+            let bytes = encode_checkpoint(&state, &config, format).unwrap();
+            let loaded = decode_checkpoint(&bytes).unwrap();
+            assert_eq!(loaded.config, Some(serde_json::to_value(&config).unwrap()));
+            let mut resumed = loaded.state;
+            assert_eq!(resumed.attacks_last_tick, state.attacks_last_tick);
+            assert_eq!(resumed.recently_dead.len(), state.recently_dead.len());
+
+            let mut uninterrupted = state.clone();
+            for _ in 0..30 {
+                uninterrupted.tick(&config);
+                resumed.tick(&config);
+            }
+            assert_eq!(
+                serde_json::to_value(&resumed).unwrap(),
+                serde_json::to_value(&uninterrupted).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_resume_with_smaller_brains() {
+        // A checkpoint saved with the default 34 inputs, resumed by a config
+        // that opts in to the terrain and climate sensors
+        let mut old = Config::default();
+        old.world.width = 40;
+        old.world.height = 40;
+        old.creature.initial_population = 80;
+        old.creature.min_reproduce_energy = 0.0;
+        old.creature.reproduce_cooldown_ticks = 0;
+        old.creature.max_population = 200;
+        old.evolution.neural_net_inputs = 34;
+        old.evolution.genome_size = 369;
+        old.simulation.seed = Some(5);
+        let state = SimulationState::new(&old);
+
+        let mut config = old.clone();
+        config.evolution.neural_net_inputs = 42;
+        config.evolution.genome_size = 433;
+
+        for format in [CheckpointFormat::Binary, CheckpointFormat::Json] {
+            let bytes = encode_checkpoint(&state, &old, format).unwrap();
+            let mut resumed = decode_checkpoint(&bytes).unwrap().state;
+            resumed.prepare_resume(&config);
+
+            for _ in 0..20 {
+                resumed.tick(&config);
+            }
+            assert!(resumed.total_births > 0);
+            assert!(resumed.creatures.values().all(|c| c.brain.dimensions() == (34, 8, 12)));
+        }
+    }
+
+    #[test]
+    fn test_falls_back_to_previous_checkpoint() {
+        let directory = "test_checkpoints_fallback_temp";
+        let config = Config {
+            checkpoint: crate::config::CheckpointConfig {
+                directory: directory.to_string(),
+                ..Config::default().checkpoint
+            },
+            ..Config::default()
+        };
+
+        let mut state = SimulationState::new(&config);
+        state.tick = 10;
+        save_checkpoint(&state, &config).unwrap();
+
+        // Newer checkpoint cut short mid-write
+        let newer = storage::create_checkpoint_path(directory, 20, CheckpointFormat::Binary);
+        let bytes = encode_checkpoint(&state, &config, CheckpointFormat::Binary).unwrap();
+        fs::write(&newer, &bytes[..bytes.len() / 2]).unwrap();
+        assert_eq!(storage::find_latest_checkpoint(directory), Some(newer.clone()));
+
+        let loaded = load_checkpoint(&config).unwrap().unwrap();
+        assert_eq!(loaded.tick, 10);
+
+        // With only a corrupt checkpoint left, loading fails instead of starting fresh
+        for checkpoint in storage::list_checkpoints(directory) {
+            if checkpoint.path != newer {
+                fs::remove_file(checkpoint.path).unwrap();
+            }
+        }
+        assert!(load_checkpoint(&config).is_err());
+
+        let _ = fs::remove_dir_all(directory);
+    }
 }
@@ -0,0 +1,92 @@
+use crate::config::CheckpointConfig;
+use crate::simulation::SimulationState;
+use std::time::{Duration, Instant};
+
+/// Decides when the next periodic checkpoint is due. A checkpoint is written
+/// when any configured trigger fires: wall-clock time, ticks, or generations.
+#[derive(Debug, Clone)]
+pub struct CheckpointSchedule {
+    interval: Duration,
+    interval_ticks: Option<u64>,
+    interval_generations: Option<u64>,
+    last_saved: Instant,
+    last_tick: u64,
+    last_generation: u64,
+}
+
+impl CheckpointSchedule {
+    pub fn new(config: &CheckpointConfig, state: &SimulationState) -> Self {
+        Self {
+            interval: Duration::from_secs(config.interval_seconds),
+            interval_ticks: config.interval_ticks.filter(|n| *n > 0),
+            interval_generations: config.interval_generations.filter(|n| *n > 0),
+            last_saved: Instant::now(),
+            last_tick: state.tick,
+            last_generation: state.max_generation(),
+        }
+    }
+
+    pub fn is_due(&self, state: &SimulationState) -> bool {
+        if self.last_saved.elapsed() >= self.interval {
+            return true;
+        }
+
+        if let Some(ticks) = self.interval_ticks {
+            if state.tick / ticks > self.last_tick / ticks {
+                return true;
+            }
+        }
+
+        if let Some(generations) = self.interval_generations {
+            if state.max_generation() / generations > self.last_generation / generations {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Pick up changed intervals without resetting progress towards them
+    pub fn reconfigure(&mut self, config: &CheckpointConfig) {
+        self.interval = Duration::from_secs(config.interval_seconds);
+        self.interval_ticks = config.interval_ticks.filter(|n| *n > 0);
+        self.interval_generations = config.interval_generations.filter(|n| *n > 0);
+    }
+
+    /// Restart every trigger from the state that was just saved
+    pub fn mark_saved(&mut self, state: &SimulationState) {
+        self.last_saved = Instant::now();
+        self.last_tick = state.tick;
+        self.last_generation = state.max_generation();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_tick_and_generation_triggers() {
+        let mut config = Config::default();
+        config.world.width = 20;
+        config.world.height = 20;
+        config.checkpoint.interval_ticks = Some(100);
+        config.checkpoint.interval_generations = Some(5);
+
+        let mut state = SimulationState::new(&config);
+        let mut schedule = CheckpointSchedule::new(&config.checkpoint, &state);
+        assert!(!schedule.is_due(&state));
+
+        state.tick = 99;
+        assert!(!schedule.is_due(&state));
+        state.tick = 100;
+        assert!(schedule.is_due(&state));
+        schedule.mark_saved(&state);
+        assert!(!schedule.is_due(&state));
+
+        let creature = state.creatures.values_mut().next().unwrap();
+        creature.genome.generation = 5;
+        assert!(schedule.is_due(&state));
+    }
+}
@@ -1,67 +1,211 @@
-use chrono::{DateTime, Utc};
-use std::fs;
+use crate::config::{CheckpointFormat, RetentionPolicy};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
 
-pub fn find_latest_checkpoint(directory: &str) -> Option<PathBuf> {
-    let dir = Path::new(directory);
-    if !dir.exists() {
-        return None;
-    }
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+const TEMP_EXTENSION: &str = "tmp";
+/// Temporary files untouched for this long were left by an interrupted
+/// write. Younger ones may belong to a save still in progress.
+const STALE_TEMP_AGE: std::time::Duration = std::time::Duration::from_secs(600);
 
-    let mut checkpoints: Vec<(PathBuf, DateTime<Utc>)> = Vec::new();
+/// Numbers the temporary files of this process, so concurrent writes of the
+/// same checkpoint never share one
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A checkpoint file found on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointEntry {
+    pub path: PathBuf,
+    /// Tick encoded in the file name, absent for files named before ticks were included
+    pub tick: Option<u64>,
+    /// Time the checkpoint was written, from its name or else its modification time
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Parse `checkpoint_<tick>_<timestamp>` or the older `checkpoint_<timestamp>`
+fn parse_checkpoint_name(stem: &str) -> Option<(Option<u64>, DateTime<Utc>)> {
+    let rest = stem.strip_prefix("checkpoint_")?;
+
+    let (tick, timestamp) = match rest.split_once('_') {
+        Some((tick, timestamp)) if tick.chars().all(|c| c.is_ascii_digit()) && !timestamp.is_empty() => {
+            (Some(tick.parse().ok()?), timestamp)
+        }
+        _ => (None, rest),
+    };
+
+    let timestamp = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+    Some((tick, timestamp.and_utc()))
+}
+
+/// List checkpoint files of either format, newest first.
+///
+/// Files are ordered by the time and tick in their names so that copying or
+/// touching a directory doesn't change which checkpoint is considered latest.
+/// Names that don't follow the pattern fall back to their modification time.
+pub fn list_checkpoints(directory: &str) -> Vec<CheckpointEntry> {
+    let dir = Path::new(directory);
+    let mut checkpoints: Vec<CheckpointEntry> = Vec::new();
 
     if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                if let Ok(metadata) = entry.metadata() {
-                    if let Ok(modified) = metadata.modified() {
-                        let datetime: DateTime<Utc> = modified.into();
-                        checkpoints.push((path, datetime));
-                    }
-                }
+            let is_checkpoint = path
+                .extension()
+                .and_then(|s| s.to_str())
+                .and_then(CheckpointFormat::from_extension)
+                .is_some();
+            if !is_checkpoint {
+                continue;
             }
+
+            let parsed = path.file_stem().and_then(|s| s.to_str()).and_then(parse_checkpoint_name);
+            let (tick, timestamp) = match parsed {
+                Some(parsed) => parsed,
+                None => match entry.metadata().and_then(|m| m.modified()) {
+                    Ok(modified) => (None, modified.into()),
+                    Err(_) => continue,
+                },
+            };
+
+            checkpoints.push(CheckpointEntry { path, tick, timestamp });
         }
     }
 
-    checkpoints.sort_by(|a, b| b.1.cmp(&a.1));
-    checkpoints.first().map(|(path, _)| path.clone())
+    checkpoints.sort_by_key(|c| std::cmp::Reverse((c.timestamp, c.tick)));
+    checkpoints
+}
+
+pub fn find_latest_checkpoint(directory: &str) -> Option<PathBuf> {
+    list_checkpoints(directory).into_iter().next().map(|c| c.path)
 }
 
-pub fn create_checkpoint_path(directory: &str) -> PathBuf {
+pub fn create_checkpoint_path(directory: &str, tick: u64, format: CheckpointFormat) -> PathBuf {
     let now: DateTime<Utc> = Utc::now();
-    let filename = format!("checkpoint_{}.json", now.format("%Y-%m-%d_%H-%M-%S"));
+    let filename = format!(
+        "checkpoint_{:012}_{}.{}",
+        tick,
+        now.format(TIMESTAMP_FORMAT),
+        format.extension()
+    );
 
     Path::new(directory).join(filename)
 }
 
-pub fn cleanup_old_checkpoints(directory: &str, keep_last_n: usize) -> Result<(), Box<dyn std::error::Error>> {
-    let dir = Path::new(directory);
-    if !dir.exists() {
-        return Ok(());
+/// Write `bytes` to `path` so that a crash leaves either the old file or the
+/// complete new one, never a partial write.
+///
+/// The data goes to a temporary file next to the target, is flushed to disk,
+/// and is then renamed over the target. The temporary name is unique to the
+/// process and the write, so concurrent saves don't touch each other's files.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut temp_name = path.file_name().ok_or("Checkpoint path has no file name")?.to_os_string();
+    temp_name.push(format!(
+        ".{}.{}.{}",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+        TEMP_EXTENSION
+    ));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| -> std::io::Result<()> {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
     }
 
-    let mut checkpoints: Vec<(PathBuf, DateTime<Utc>)> = Vec::new();
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        File::open(parent)?.sync_all()?;
+    }
 
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                if let Ok(metadata) = entry.metadata() {
-                    if let Ok(modified) = metadata.modified() {
-                        let datetime: DateTime<Utc> = modified.into();
-                        checkpoints.push((path, datetime));
-                    }
-                }
-            }
+    Ok(())
+}
+
+/// Pick which of `checkpoints` (newest first) to keep. The newest
+/// `keep_last_n` are always kept; `retention` can keep more of the history.
+pub fn retained_checkpoints(
+    checkpoints: &[CheckpointEntry],
+    keep_last_n: usize,
+    retention: Option<&RetentionPolicy>,
+    now: DateTime<Utc>,
+) -> Vec<bool> {
+    let mut keep: Vec<bool> = (0..checkpoints.len()).map(|i| i < keep_last_n).collect();
+    let Some(policy) = retention else {
+        return keep;
+    };
+
+    let mut days_seen: HashSet<NaiveDate> = HashSet::new();
+    let mut weeks_seen: HashSet<(i32, u32)> = HashSet::new();
+
+    for (checkpoint, keep) in checkpoints.iter().zip(keep.iter_mut()) {
+        let age = now - checkpoint.timestamp;
+        let day = checkpoint.timestamp.date_naive();
+        let week = checkpoint.timestamp.iso_week();
+
+        // Iterating newest first, the first checkpoint seen in a day or
+        // week is the newest one in it
+        let newest_of_day = days_seen.insert(day);
+        let newest_of_week = weeks_seen.insert((week.year(), week.week()));
+
+        if age <= Duration::hours(policy.keep_all_hours as i64) {
+            *keep = true;
+        }
+        if newest_of_day && age <= Duration::days(policy.keep_daily_days as i64) {
+            *keep = true;
+        }
+        if newest_of_week && policy.keep_weekly_weeks.is_none_or(|weeks| age <= Duration::weeks(weeks as i64)) {
+            *keep = true;
         }
     }
 
-    checkpoints.sort_by(|a, b| b.1.cmp(&a.1));
+    keep
+}
+
+pub fn cleanup_old_checkpoints(
+    directory: &str,
+    keep_last_n: usize,
+    retention: Option<&RetentionPolicy>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let checkpoints = list_checkpoints(directory);
+    let keep = retained_checkpoints(&checkpoints, keep_last_n, retention, Utc::now());
+    for (checkpoint, keep) in checkpoints.iter().zip(keep) {
+        if !keep {
+            log::info!("Deleting old checkpoint: {:?}", checkpoint.path);
+            let _ = fs::remove_file(&checkpoint.path);
+        }
+    }
 
-    for (path, _) in checkpoints.iter().skip(keep_last_n) {
-        log::info!("Deleting old checkpoint: {:?}", path);
-        let _ = fs::remove_file(path);
+    // Temporary files are left behind by writes interrupted by a crash. A
+    // recent one may still be in use by a concurrent save.
+    if let Ok(entries) = fs::read_dir(directory) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some(TEMP_EXTENSION) {
+                continue;
+            }
+            let stale = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age >= STALE_TEMP_AGE);
+            if stale {
+                log::info!("Deleting incomplete checkpoint: {:?}", path);
+                let _ = fs::remove_file(&path);
+            }
+        }
     }
 
     Ok(())
@@ -73,9 +217,112 @@ mod tests {
 
     #[test]
     fn test_create_checkpoint_path() {
-        let path = create_checkpoint_path("test_checkpoints");
+        let path = create_checkpoint_path("test_checkpoints", 42, CheckpointFormat::Json);
         assert!(path.to_str().unwrap().starts_with("test_checkpoints"));
         assert!(path.to_str().unwrap().ends_with(".json"));
-        assert!(path.to_str().unwrap().contains("checkpoint_"));
+        assert!(path.to_str().unwrap().contains("checkpoint_000000000042_"));
+
+        let path = create_checkpoint_path("test_checkpoints", 42, CheckpointFormat::Binary);
+        assert!(path.to_str().unwrap().ends_with(".evc"));
+
+        let (tick, _) = parse_checkpoint_name(path.file_stem().unwrap().to_str().unwrap()).unwrap();
+        assert_eq!(tick, Some(42));
+    }
+
+    #[test]
+    fn test_checkpoints_ordered_by_name() {
+        let directory = "test_checkpoint_order_temp";
+        fs::create_dir_all(directory).unwrap();
+        for name in [
+            "checkpoint_2024-01-01_00-00-00.json",
+            "checkpoint_000000000500_2024-01-02_00-00-00.evc",
+            "checkpoint_000000000400_2024-01-02_00-00-00.evc",
+        ] {
+            write_atomic(&Path::new(directory).join(name), b"x").unwrap();
+        }
+
+        let ticks: Vec<Option<u64>> = list_checkpoints(directory).iter().map(|c| c.tick).collect();
+        assert_eq!(ticks, vec![Some(500), Some(400), None]);
+
+        let _ = fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn test_concurrent_saves() {
+        let directory = std::env::temp_dir().join(format!("evo_concurrent_saves_{}", std::process::id()));
+        let mut config = crate::config::Config::default();
+        config.world.width = 20;
+        config.world.height = 20;
+        config.creature.initial_population = 10;
+        config.checkpoint.directory = directory.to_string_lossy().to_string();
+        let state = crate::simulation::SimulationState::new(&config);
+
+        // Both threads save the same tick, so they race for the same name
+        std::thread::scope(|scope| {
+            let saves: Vec<_> = (0..2)
+                .map(|_| {
+                    scope.spawn(|| {
+                        for _ in 0..20 {
+                            crate::checkpoint::save_checkpoint(&state, &config).map_err(|e| e.to_string())?;
+                        }
+                        Ok::<(), String>(())
+                    })
+                })
+                .collect();
+            for save in saves {
+                assert_eq!(save.join().unwrap(), Ok(()));
+            }
+        });
+
+        let leftovers = fs::read_dir(&directory)
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.path().extension().and_then(|s| s.to_str()) == Some(TEMP_EXTENSION))
+            .count();
+        assert_eq!(leftovers, 0);
+        assert!(!list_checkpoints(&directory.to_string_lossy()).is_empty());
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_tiered_retention() {
+        let now = NaiveDateTime::parse_from_str("2024-06-30_12-00-00", TIMESTAMP_FORMAT)
+            .unwrap()
+            .and_utc();
+
+        // Hourly checkpoints for 90 days, newest first
+        let checkpoints: Vec<CheckpointEntry> = (0..90 * 24)
+            .map(|hours| CheckpointEntry {
+                path: PathBuf::from(format!("checkpoint_{}.evc", hours)),
+                tick: None,
+                timestamp: now - Duration::hours(hours),
+            })
+            .collect();
+
+        let policy = RetentionPolicy {
+            keep_all_hours: 24,
+            keep_daily_days: 30,
+            keep_weekly_weeks: None,
+        };
+        let keep = retained_checkpoints(&checkpoints, 5, Some(&policy), now);
+        let kept: Vec<&CheckpointEntry> = checkpoints.iter().zip(&keep).filter(|(_, k)| **k).map(|(c, _)| c).collect();
+
+        // Everything from the last day is kept
+        assert!(keep[..=24].iter().all(|k| *k));
+        // At most one per day between one and thirty days old
+        let daily: Vec<_> = kept
+            .iter()
+            .filter(|c| now - c.timestamp > Duration::hours(24) && now - c.timestamp <= Duration::days(30))
+            .collect();
+        assert!(daily.len() <= 30 && daily.len() >= 28);
+        // Older history thins out to one per week, but is never dropped entirely
+        let weekly = kept.iter().filter(|c| now - c.timestamp > Duration::days(30)).count();
+        assert!((8..=10).contains(&weekly));
+        assert!(kept.iter().any(|c| now - c.timestamp > Duration::days(83)));
+
+        // Without a policy only keep_last_n survive
+        let keep = retained_checkpoints(&checkpoints, 5, None, now);
+        assert_eq!(keep.iter().filter(|k| **k).count(), 5);
     }
 }
@@ -0,0 +1,546 @@
+pub mod overrides;
+pub mod reload;
+pub mod schedule;
+pub mod validate;
+
+use schedule::ScheduleConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::world::{Fertility, Topology};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub world: WorldConfig,
+    pub creature: CreatureConfig,
+    pub evolution: EvolutionConfig,
+    pub combat: CombatConfig,
+    pub simulation: SimulationConfig,
+    pub checkpoint: CheckpointConfig,
+    pub server: ServerConfig,
+    /// Numeric fields that change over the course of a run
+    pub schedule: ScheduleConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldConfig {
+    pub width: usize,
+    pub height: usize,
+    pub initial_food_density: f64,
+    pub food_regen_rate: f64,
+    pub max_food_per_cell: u32,
+    pub plant_decay_ticks: u32,
+    pub meat_decay_ticks: u32,
+    /// What happens at the edges of the grid
+    pub topology: Topology,
+    pub terrain: TerrainConfig,
+    pub biomes: BiomeConfig,
+    pub climate: ClimateConfig,
+    pub plants: PlantConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlantConfig {
+    pub mode: PlantMode,
+    /// Logistic growth rate per tick of each plant towards its biome's
+    /// `max_food_per_cell`, in ecology mode
+    pub growth_rate: f64,
+    /// Chance per tick that a full plant seeds a random neighbouring cell,
+    /// scaled down for emptier plants, in ecology mode
+    pub seed_rate: f64,
+}
+
+/// How plant food comes and goes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlantMode {
+    /// Single units dropped on random cells that decay after
+    /// `plant_decay_ticks`
+    #[default]
+    Random,
+    /// Plants grow in place, seed their neighbours and never age out. Random
+    /// drops still happen at `food_regen_rate`, as wind-blown seeds.
+    Ecology,
+}
+
+/// Seasons and the day/night cycle. A length of 0 turns its cycle off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClimateConfig {
+    /// Ticks in a year, starting at the beginning of spring
+    pub season_length_ticks: u64,
+    /// Ticks in a day, starting at dawn
+    pub day_length_ticks: u64,
+    /// How far food regrowth rises above normal in midsummer and falls
+    /// below it in midwinter, as a fraction
+    pub season_food_amplitude: f64,
+    /// How far the per-tick energy cost rises above normal in midwinter and
+    /// falls below it in midsummer, as a fraction
+    pub season_metabolism_amplitude: f64,
+    /// Fraction of the usual sensing range left at midnight
+    pub night_sensor_range: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BiomeConfig {
+    /// Biomes by index. Empty for a single biome using the `world` food
+    /// settings.
+    pub kinds: Vec<BiomeKind>,
+    /// ASCII or PNG map choosing each cell's biome, stretched over the grid.
+    /// None lays the biomes out from noise.
+    pub map: Option<String>,
+    /// Seed for the noise layout; None uses the simulation seed
+    pub seed: Option<u64>,
+    /// Rough width of a noise patch, in cells
+    pub noise_scale: f64,
+}
+
+/// Food settings of one biome. Unset fields take the `world` value of the
+/// same name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BiomeKind {
+    pub name: String,
+    pub initial_food_density: Option<f64>,
+    pub food_regen_rate: Option<f64>,
+    pub max_food_per_cell: Option<u32>,
+    pub plant_decay_ticks: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerrainConfig {
+    /// ASCII or PNG map of walls, water and rough ground, stretched over the
+    /// grid. None leaves every cell plain.
+    pub map: Option<String>,
+    /// Energy on top of the move cost for moving into water
+    pub water_move_cost: f64,
+    /// Energy on top of the move cost for moving onto rough ground
+    pub rough_move_cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CreatureConfig {
+    pub initial_population: usize,
+    pub max_population: usize,
+    pub initial_energy: f64,
+    pub max_energy: f64,
+    /// Energy per unit of plant food, before digestion
+    pub energy_per_food: f64,
+    /// Energy per unit of meat, before digestion
+    pub energy_per_meat: f64,
+    /// Fraction of a food's energy that a complete specialist in the other
+    /// food still digests. 1 turns the plant/meat trade-off off.
+    pub off_diet_efficiency: f64,
+    pub energy_cost_per_tick: f64,
+    pub energy_cost_move: f64,
+    pub energy_cost_reproduce: f64,
+    pub min_reproduce_energy: f64,
+    pub reproduce_cooldown_ticks: u64,
+    pub max_age_ticks: u64,
+    pub energy_cost_sprint: f64,
+    pub energy_share_amount: f64,
+    pub rest_energy_multiplier: f64,
+    pub rest_healing_multiplier: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvolutionConfig {
+    pub mutation_rate: f64,
+    pub genome_size: usize,
+    pub neural_net_inputs: usize,
+    pub neural_net_hidden: usize,
+    pub neural_net_outputs: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CombatConfig {
+    pub damage_per_attack: f64,
+    pub damage_per_strong_attack: f64,
+    pub health_regen_rate: f64,
+    pub health_regen_energy_cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    pub ticks_per_second: u64,
+    pub log_interval_ticks: u64,
+    /// Seed for all simulation randomness. When unset a random seed is drawn
+    /// at startup and logged so the run can be reproduced.
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckpointConfig {
+    pub enabled: bool,
+    pub interval_seconds: u64,
+    /// Also checkpoint every N ticks
+    pub interval_ticks: Option<u64>,
+    /// Also checkpoint each time the max generation passes a multiple of N
+    pub interval_generations: Option<u64>,
+    pub directory: String,
+    pub keep_last_n: usize,
+    /// Tiered retention on top of `keep_last_n`; without it only the newest
+    /// `keep_last_n` checkpoints are kept
+    pub retention: Option<RetentionPolicy>,
+    /// Record every tick's events to an append-only journal in `directory`
+    pub journal_enabled: bool,
+    pub format: CheckpointFormat,
+}
+
+/// Which older checkpoints survive cleanup. A checkpoint is kept if any tier
+/// selects it or it is among the newest `keep_last_n`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Keep every checkpoint written within this many hours
+    pub keep_all_hours: u64,
+    /// Keep the newest checkpoint of each day for this many days
+    pub keep_daily_days: u64,
+    /// Keep the newest checkpoint of each week for this many weeks, or forever when unset
+    pub keep_weekly_weeks: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_all_hours: 24,
+            keep_daily_days: 30,
+            keep_weekly_weeks: None,
+        }
+    }
+}
+
+/// On-disk checkpoint encoding. Both formats can always be loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointFormat {
+    /// Compressed binary with a versioned header; brains are rebuilt from genomes
+    #[default]
+    Binary,
+    /// Pretty-printed JSON of the whole simulation state
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub enabled: bool,
+    pub address: String,
+    pub port: u16,
+    pub update_rate_hz: u64,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            width: 300,
+            height: 300,
+            initial_food_density: 0.3,
+            food_regen_rate: 0.001,
+            max_food_per_cell: 10,
+            plant_decay_ticks: 600, // ~20 seconds at 30 TPS
+            meat_decay_ticks: 300,  // ~10 seconds at 30 TPS (faster decay)
+            topology: Topology::Bounded,
+            terrain: TerrainConfig::default(),
+            biomes: BiomeConfig::default(),
+            climate: ClimateConfig::default(),
+            plants: PlantConfig::default(),
+        }
+    }
+}
+
+impl Default for PlantConfig {
+    fn default() -> Self {
+        Self {
+            mode: PlantMode::Random,
+            growth_rate: 0.02,
+            seed_rate: 0.01,
+        }
+    }
+}
+
+impl Default for ClimateConfig {
+    fn default() -> Self {
+        Self {
+            season_length_ticks: 0,
+            day_length_ticks: 0,
+            season_food_amplitude: 0.5,
+            season_metabolism_amplitude: 0.25,
+            night_sensor_range: 0.5,
+        }
+    }
+}
+
+impl Default for BiomeConfig {
+    fn default() -> Self {
+        Self {
+            kinds: Vec::new(),
+            map: None,
+            seed: None,
+            noise_scale: 40.0,
+        }
+    }
+}
+
+impl WorldConfig {
+    /// Food settings of each biome, by index. A world without biomes has one.
+    pub fn fertility(&self) -> Vec<Fertility> {
+        let base = Fertility {
+            initial_food_density: self.initial_food_density,
+            food_regen_rate: self.food_regen_rate,
+            max_food_per_cell: self.max_food_per_cell,
+            plant_decay_ticks: self.plant_decay_ticks,
+        };
+        if self.biomes.kinds.is_empty() {
+            return vec![base];
+        }
+        self.biomes
+            .kinds
+            .iter()
+            .map(|kind| Fertility {
+                initial_food_density: kind.initial_food_density.unwrap_or(base.initial_food_density),
+                food_regen_rate: kind.food_regen_rate.unwrap_or(base.food_regen_rate),
+                max_food_per_cell: kind.max_food_per_cell.unwrap_or(base.max_food_per_cell),
+                plant_decay_ticks: kind.plant_decay_ticks.unwrap_or(base.plant_decay_ticks),
+            })
+            .collect()
+    }
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self {
+            map: None,
+            water_move_cost: 2.0,
+            rough_move_cost: 0.5,
+        }
+    }
+}
+
+impl Default for CreatureConfig {
+    fn default() -> Self {
+        Self {
+            initial_population: 100,
+            max_population: 10000,
+            initial_energy: 100.0,
+            max_energy: 200.0,
+            energy_per_food: 20.0,
+            energy_per_meat: 20.0,
+            off_diet_efficiency: 1.0,     // No plant/meat trade-off unless asked for
+            energy_cost_per_tick: 0.1,
+            energy_cost_move: 1.0,
+            energy_cost_reproduce: 50.0,
+            min_reproduce_energy: 100.0,
+            reproduce_cooldown_ticks: 100,
+            max_age_ticks: 10000,         // ~5.5 minutes at 30 TPS
+            energy_cost_sprint: 2.0,      // 2x normal movement cost
+            energy_share_amount: 20.0,    // Amount shared per action
+            rest_energy_multiplier: 0.5,  // Reduced energy consumption when resting
+            rest_healing_multiplier: 2.0, // Boosted healing when resting
+        }
+    }
+}
+
+impl Default for EvolutionConfig {
+    fn default() -> Self {
+        Self {
+            mutation_rate: 0.01,
+            genome_size: 369,       // Network weights plus the digestion gene
+            neural_net_inputs: 34,  // 16 original + 14 sensors + 4 directional food; the terrain and climate sensors are opt-in
+            neural_net_hidden: 8,   // Increased for more complexity
+            neural_net_outputs: 12, // 4 moves + 8 new actions
+        }
+    }
+}
+
+impl Default for CombatConfig {
+    fn default() -> Self {
+        Self {
+            damage_per_attack: 20.0,
+            damage_per_strong_attack: 40.0, // 2x normal attack damage
+            health_regen_rate: 2.0,
+            health_regen_energy_cost: 2.0,
+        }
+    }
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            ticks_per_second: 30,
+            log_interval_ticks: 300,
+            seed: None,
+        }
+    }
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_seconds: 3600,
+            interval_ticks: None,
+            interval_generations: None,
+            directory: "checkpoints".to_string(),
+            keep_last_n: 24,
+            retention: None,
+            journal_enabled: false,
+            format: CheckpointFormat::Binary,
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            address: "0.0.0.0".to_string(),
+            port: 8080,
+            update_rate_hz: 10,
+        }
+    }
+}
+
+/// What `Config::parse` had to fill in or ignore
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// Dotted paths of fields missing from the file and taken from the defaults
+    pub defaulted_fields: Vec<String>,
+    /// Dotted paths of keys in the file that no config field reads
+    pub unknown_fields: Vec<String>,
+}
+
+impl LoadReport {
+    fn new(file: &serde_json::Value, config: &Config) -> Result<Self, serde_json::Error> {
+        let mut report = Self::default();
+        let reference = serde_json::to_value(config)?;
+        compare_keys(file, &reference, "", &mut report.unknown_fields);
+        compare_keys(&reference, file, "", &mut report.defaulted_fields);
+        Ok(report)
+    }
+}
+
+/// Collect paths of keys present in `value` but not in `reference`. Only
+/// objects on both sides are compared, so a null default (an unset optional
+/// section) accepts whatever the file puts there.
+fn compare_keys(value: &serde_json::Value, reference: &serde_json::Value, prefix: &str, missing: &mut Vec<String>) {
+    let (Some(value), Some(reference)) = (value.as_object(), reference.as_object()) else {
+        return;
+    };
+
+    for (key, child) in value {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match reference.get(key) {
+            Some(reference_child) => compare_keys(child, reference_child, &path, missing),
+            None => missing.push(path),
+        }
+    }
+}
+
+impl Config {
+    /// Parse a config file, filling any missing field from its default.
+    /// Unknown keys are ignored and reported rather than rejected.
+    pub fn parse(content: &str) -> Result<(Self, LoadReport), serde_json::Error> {
+        let value: serde_json::Value = serde_json::from_str(content)?;
+        let config: Config = serde_json::from_value(value.clone())?;
+        let report = LoadReport::new(&value, &config)?;
+        Ok((config, report))
+    }
+
+    /// Load a config file, logging which fields were defaulted or ignored.
+    /// The file itself is never modified; see `save_to_file`.
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let (config, report) =
+            Self::parse(&content).map_err(|e| format!("Failed to parse config file {}: {}", path, e))?;
+
+        for field in &report.unknown_fields {
+            log::warn!("Unknown config field {} in {} is ignored", field, path);
+        }
+        if !report.defaulted_fields.is_empty() {
+            log::info!(
+                "{} field(s) missing from {} use defaults: {}",
+                report.defaulted_fields.len(),
+                path,
+                report.defaulted_fields.join(", ")
+            );
+        }
+
+        Ok(config)
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+        assert_eq!(config.world.width, 300);
+        assert_eq!(config.world.height, 300);
+        assert!(config.checkpoint.enabled);
+    }
+
+    #[test]
+    fn test_config_serialization() {
+        let config = Config::default();
+        let json = serde_json::to_string(&config).unwrap();
+        let deserialized: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.world.width, deserialized.world.width);
+    }
+
+    #[test]
+    fn test_partial_config_uses_defaults() {
+        let content = r#"{
+            "world": { "width": 500, "food_regen": 0.1 },
+            "checkpoint": { "retention": { "keep_all_hours": 6 } },
+            "plugins": []
+        }"#;
+        let (config, report) = Config::parse(content).unwrap();
+
+        assert_eq!(config.world.width, 500);
+        assert_eq!(config.world.height, WorldConfig::default().height);
+        assert_eq!(config.creature.initial_population, CreatureConfig::default().initial_population);
+        let retention = config.checkpoint.retention.unwrap();
+        assert_eq!(retention.keep_all_hours, 6);
+        assert_eq!(retention.keep_daily_days, RetentionPolicy::default().keep_daily_days);
+
+        assert_eq!(report.unknown_fields, vec!["plugins", "world.food_regen"]);
+        assert!(report.defaulted_fields.contains(&"world.height".to_string()));
+        assert!(report.defaulted_fields.contains(&"creature".to_string()));
+        assert!(!report.defaulted_fields.contains(&"world.width".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_config_file_is_left_alone() {
+        let path = std::env::temp_dir().join(format!("evo_invalid_config_{}.json", std::process::id()));
+        let content = r#"{ "world": { "width": "wide" } }"#;
+        std::fs::write(&path, content).unwrap();
+
+        let error = Config::load_from_file(path.to_str().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("Failed to parse config file"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,160 @@
+use super::reload::ConfigUpdateError;
+use super::Config;
+use serde_json::Value;
+use std::fmt;
+
+/// Prefix of environment variables that override config fields
+pub const ENV_PREFIX: &str = "EVO_";
+/// Separator between path segments in environment variable names
+const ENV_SEPARATOR: &str = "__";
+
+/// A single field set from outside the config file, e.g. by
+/// `EVO_WORLD__WIDTH=500` or `--set world.width=500`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigOverride {
+    /// Where the override came from, for error messages
+    pub source: String,
+    /// Dotted path of the field, e.g. `world.width`
+    pub path: String,
+    /// The value as given on the command line or in the environment
+    pub raw: String,
+}
+
+impl fmt::Display for ConfigOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}={})", self.source, self.path, self.raw)
+    }
+}
+
+impl ConfigOverride {
+    /// Parse a `--set` argument of the form `path=value`
+    pub fn from_assignment(assignment: &str) -> Result<Self, String> {
+        let (path, raw) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("Expected path=value, got {:?}", assignment))?;
+        let path = path.trim();
+        if path.is_empty() || path.split('.').any(str::is_empty) {
+            return Err(format!("Invalid config path {:?}", path));
+        }
+
+        Ok(Self {
+            source: "--set".to_string(),
+            path: path.to_string(),
+            raw: raw.to_string(),
+        })
+    }
+
+    /// Interpret an environment variable named like `EVO_WORLD__WIDTH`.
+    /// Variables without the prefix or a `__` separator are not overrides.
+    pub fn from_env_var(name: &str, raw: &str) -> Option<Self> {
+        let rest = name.strip_prefix(ENV_PREFIX)?;
+        if !rest.contains(ENV_SEPARATOR) {
+            return None;
+        }
+
+        let path = rest
+            .split(ENV_SEPARATOR)
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(".");
+        Some(Self {
+            source: name.to_string(),
+            path,
+            raw: raw.to_string(),
+        })
+    }
+
+    /// Every override set in the process environment, sorted by name
+    pub fn from_env() -> Vec<Self> {
+        let mut vars: Vec<(String, String)> = std::env::vars().collect();
+        vars.sort();
+        vars.iter()
+            .filter_map(|(name, raw)| Self::from_env_var(name, raw))
+            .collect()
+    }
+
+    /// A merge patch setting just this field to `value`
+    fn patch(&self, value: Value) -> Value {
+        self.path
+            .rsplit('.')
+            .fold(value, |inner, key| Value::Object([(key.to_string(), inner)].into_iter().collect()))
+    }
+}
+
+impl Config {
+    /// Apply overrides in order, so later ones win. Values are read as JSON
+    /// (numbers, booleans, `null`, arrays) and otherwise as plain strings.
+    pub fn with_overrides(&self, overrides: &[ConfigOverride]) -> Result<Config, String> {
+        let mut config = self.clone();
+
+        for item in overrides {
+            let parsed = serde_json::from_str(&item.raw).unwrap_or_else(|_| Value::String(item.raw.clone()));
+            let result = match config.patched(&item.patch(parsed.clone())) {
+                // `--set checkpoint.directory=2024` means the string "2024"
+                Err(ConfigUpdateError::Parse(_)) if !parsed.is_string() => {
+                    config.patched(&item.patch(Value::String(item.raw.clone())))
+                }
+                result => result,
+            };
+
+            config = match result {
+                Ok(config) => config,
+                Err(ConfigUpdateError::UnknownFields(_)) => {
+                    return Err(format!("{}: {} is not a config field", item.source, item.path))
+                }
+                Err(e) => return Err(format!("{}: {}", item, e)),
+            };
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_and_cli_overrides() {
+        assert_eq!(ConfigOverride::from_env_var("EVO_VERSION", "1"), None);
+        assert_eq!(ConfigOverride::from_env_var("HOME", "/root"), None);
+
+        let overrides = vec![
+            ConfigOverride::from_env_var("EVO_WORLD__WIDTH", "500").unwrap(),
+            ConfigOverride::from_env_var("EVO_CHECKPOINT__DIRECTORY", "/app/data/checkpoints").unwrap(),
+            ConfigOverride::from_assignment("creature.max_population=5000").unwrap(),
+            ConfigOverride::from_assignment("world.width=400").unwrap(),
+            ConfigOverride::from_assignment("server.address=127.0.0.1").unwrap(),
+            ConfigOverride::from_assignment("simulation.seed=7").unwrap(),
+            ConfigOverride::from_assignment("checkpoint.retention.keep_all_hours=6").unwrap(),
+        ];
+        assert_eq!(overrides[0].path, "world.width");
+
+        let config = Config::default().with_overrides(&overrides).unwrap();
+        assert_eq!(config.world.width, 400);
+        assert_eq!(config.world.height, 300);
+        assert_eq!(config.checkpoint.directory, "/app/data/checkpoints");
+        assert_eq!(config.creature.max_population, 5000);
+        assert_eq!(config.server.address, "127.0.0.1");
+        assert_eq!(config.simulation.seed, Some(7));
+        assert_eq!(config.checkpoint.retention.unwrap().keep_all_hours, 6);
+
+        let numeric_directory = ConfigOverride::from_assignment("checkpoint.directory=2024").unwrap();
+        let config = Config::default().with_overrides(&[numeric_directory]).unwrap();
+        assert_eq!(config.checkpoint.directory, "2024");
+    }
+
+    #[test]
+    fn test_bad_overrides_name_their_source() {
+        assert!(ConfigOverride::from_assignment("world.width").is_err());
+        assert!(ConfigOverride::from_assignment("world..width=3").is_err());
+
+        let unknown = ConfigOverride::from_env_var("EVO_WORLD__DEPTH", "5").unwrap();
+        let error = Config::default().with_overrides(&[unknown]).unwrap_err();
+        assert_eq!(error, "EVO_WORLD__DEPTH: world.depth is not a config field");
+
+        let wrong_type = ConfigOverride::from_assignment("world.width=wide").unwrap();
+        let error = Config::default().with_overrides(&[wrong_type]).unwrap_err();
+        assert!(error.starts_with("--set (world.width=wide): Invalid config"));
+    }
+}
@@ -0,0 +1,313 @@
+use super::validate::ValidationErrors;
+use super::{compare_keys, Config};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Fields that size or wire up the running simulation. Changing them needs a
+/// restart, so live updates touching them are rejected. An entry also covers
+/// every field below it.
+const RESTART_REQUIRED: &[&str] = &[
+    "world.width",
+    "world.height",
+    "world.topology",
+    "world.terrain.map",
+    "world.biomes.map",
+    "world.biomes.seed",
+    "world.biomes.noise_scale",
+    "evolution.genome_size",
+    "evolution.neural_net_inputs",
+    "evolution.neural_net_hidden",
+    "evolution.neural_net_outputs",
+    "simulation.seed",
+    "checkpoint.enabled",
+    "checkpoint.directory",
+    "checkpoint.journal_enabled",
+    "server",
+];
+
+/// True if a change to the field at `path` only takes effect after a restart
+pub fn requires_restart(path: &str) -> bool {
+    RESTART_REQUIRED.iter().any(|field| {
+        path.strip_prefix(field)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
+/// True if `new` has other biomes than `old`, or names them differently.
+/// Cells hold biome indices laid out when the world was created, so a running
+/// world can change each biome's food settings but not which biomes exist.
+fn changes_biome_set(old: &Config, new: &Config) -> bool {
+    let names = |config: &Config| -> Vec<String> {
+        config.world.biomes.kinds.iter().map(|kind| kind.name.clone()).collect()
+    };
+    names(old) != names(new)
+}
+
+/// Sections read by `SimulationState::tick`. Changes elsewhere, such as
+/// speed or checkpoint settings, don't alter the tick history.
+const SIMULATION_SECTIONS: &[&str] = &["world", "creature", "evolution", "combat", "schedule"];
+
+/// True if a change to the field at `path` changes how ticks play out
+pub fn affects_simulation(path: &str) -> bool {
+    let section = path.split('.').next().unwrap_or_default();
+    SIMULATION_SECTIONS.contains(&section)
+}
+
+/// One field that differs between two configs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.path, self.old, self.new)
+    }
+}
+
+/// A config change that was applied to the running simulation
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigUpdate {
+    /// First tick run with the new config
+    pub tick: u64,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Why a live config change was not applied
+#[derive(Debug, Clone)]
+pub enum ConfigUpdateError {
+    /// The new config could not be deserialized
+    Parse(String),
+    /// A patch named fields that don't exist
+    UnknownFields(Vec<String>),
+    Invalid(ValidationErrors),
+    /// The change touches fields that can't be changed while running
+    RestartRequired(Vec<FieldChange>),
+}
+
+impl fmt::Display for ConfigUpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigUpdateError::Parse(e) => write!(f, "Invalid config: {}", e),
+            ConfigUpdateError::UnknownFields(fields) => {
+                write!(f, "Unknown config field(s): {}", fields.join(", "))
+            }
+            ConfigUpdateError::Invalid(errors) => write!(f, "{}", errors),
+            ConfigUpdateError::RestartRequired(changes) => {
+                write!(f, "These fields can only be changed by restarting the simulation:")?;
+                for change in changes {
+                    write!(f, "\n  {}", change)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigUpdateError {}
+
+fn to_value(config: &Config) -> Value {
+    serde_json::to_value(config).expect("config serializes to JSON")
+}
+
+/// Collect leaf fields that differ between `old` and `new`
+fn diff_values(old: &Value, new: &Value, prefix: &str, changes: &mut Vec<FieldChange>) {
+    if let (Some(old_fields), Some(new_fields)) = (old.as_object(), new.as_object()) {
+        let mut keys: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        for key in keys {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            diff_values(
+                old_fields.get(key).unwrap_or(&Value::Null),
+                new_fields.get(key).unwrap_or(&Value::Null),
+                &path,
+                changes,
+            );
+        }
+    } else if old != new {
+        changes.push(FieldChange {
+            path: prefix.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        });
+    }
+}
+
+/// Apply a JSON merge patch (RFC 7396): objects merge recursively, `null`
+/// removes a field so it falls back to its default, anything else replaces.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Some(patch_fields) = patch.as_object() else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let target_fields = target.as_object_mut().expect("target was just made an object");
+
+    for (key, value) in patch_fields {
+        if value.is_null() {
+            target_fields.remove(key);
+        } else {
+            merge_patch(target_fields.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+impl Config {
+    /// Every field that differs from `self` in `new`, in path order
+    pub fn diff(&self, new: &Config) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+        diff_values(&to_value(self), &to_value(new), "", &mut changes);
+        changes
+    }
+
+    /// Check that the running simulation can switch from `self` to `new`,
+    /// returning the fields that would change
+    pub fn live_changes(&self, new: &Config) -> Result<Vec<FieldChange>, ConfigUpdateError> {
+        new.validate().map_err(ConfigUpdateError::Invalid)?;
+
+        let changes = self.diff(new);
+        let restart_required: Vec<FieldChange> = changes
+            .iter()
+            .filter(|c| requires_restart(&c.path) || (c.path == "world.biomes.kinds" && changes_biome_set(self, new)))
+            .cloned()
+            .collect();
+        if !restart_required.is_empty() {
+            return Err(ConfigUpdateError::RestartRequired(restart_required));
+        }
+
+        Ok(changes)
+    }
+
+    /// A copy of `self` with a JSON merge patch applied. Unlike config files,
+    /// patches naming unknown fields are rejected, since they were meant to
+    /// change something.
+    pub fn patched(&self, patch: &Value) -> Result<Config, ConfigUpdateError> {
+        let mut value = to_value(self);
+        let mut unknown = Vec::new();
+        compare_keys(patch, &value, "", &mut unknown);
+        if !unknown.is_empty() {
+            return Err(ConfigUpdateError::UnknownFields(unknown));
+        }
+
+        merge_patch(&mut value, patch);
+        serde_json::from_value(value).map_err(|e| ConfigUpdateError::Parse(e.to_string()))
+    }
+}
+
+/// The config the simulation is currently running with. The tick loop
+/// publishes every applied change; the server reads the latest one.
+#[derive(Debug, Clone)]
+pub struct LiveConfig {
+    sender: watch::Sender<Arc<Config>>,
+}
+
+impl LiveConfig {
+    pub fn new(config: Config) -> Self {
+        let (sender, _) = watch::channel(Arc::new(config));
+        Self { sender }
+    }
+
+    pub fn get(&self) -> Arc<Config> {
+        self.sender.borrow().clone()
+    }
+
+    pub fn set(&self, config: Config) {
+        self.sender.send_replace(Arc::new(config));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_patch_applies_safe_fields() {
+        let config = Config::default();
+        let patch = json!({
+            "evolution": { "mutation_rate": 0.05 },
+            "combat": { "damage_per_attack": 30.0 },
+            "checkpoint": { "interval_ticks": 1000 }
+        });
+
+        let patched = config.patched(&patch).unwrap();
+        let changes = config.live_changes(&patched).unwrap();
+
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["checkpoint.interval_ticks", "combat.damage_per_attack", "evolution.mutation_rate"]
+        );
+        assert_eq!(changes[0].old, Value::Null);
+        assert_eq!(changes[0].new, json!(1000));
+        assert_eq!(patched.world.width, config.world.width);
+
+        // null resets a field to its default
+        let reset = patched.patched(&json!({ "checkpoint": { "interval_ticks": null } })).unwrap();
+        assert_eq!(reset.checkpoint.interval_ticks, None);
+    }
+
+    #[test]
+    fn test_rejects_structural_unknown_and_invalid_changes() {
+        let config = Config::default();
+
+        let resized = config.patched(&json!({ "world": { "width": 500 }, "server": { "port": 9000 } })).unwrap();
+        match config.live_changes(&resized) {
+            Err(ConfigUpdateError::RestartRequired(changes)) => {
+                let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+                assert_eq!(paths, vec!["server.port", "world.width"]);
+            }
+            other => panic!("expected RestartRequired, got {:?}", other),
+        }
+
+        match config.patched(&json!({ "world": { "widht": 500 } })) {
+            Err(ConfigUpdateError::UnknownFields(fields)) => assert_eq!(fields, vec!["world.widht"]),
+            other => panic!("expected UnknownFields, got {:?}", other),
+        }
+
+        let invalid = config.patched(&json!({ "evolution": { "mutation_rate": 2.0 } })).unwrap();
+        assert!(matches!(config.live_changes(&invalid), Err(ConfigUpdateError::Invalid(_))));
+
+        assert!(requires_restart("server.port"));
+        assert!(affects_simulation("schedule.evolution.mutation_rate"));
+        assert!(!affects_simulation("simulation.ticks_per_second"));
+        assert!(!requires_restart("world.width_scale"));
+    }
+
+    #[test]
+    fn test_biomes_keep_their_layout() {
+        let mut config = Config::default();
+        config.world.biomes.kinds = serde_json::from_value(json!([{ "name": "desert" }, { "name": "forest" }])).unwrap();
+
+        let richer = config
+            .patched(&json!({ "world": { "biomes": { "kinds": [
+                { "name": "desert", "food_regen_rate": 0.0001 },
+                { "name": "forest" }
+            ] } } }))
+            .unwrap();
+        assert!(config.live_changes(&richer).is_ok());
+
+        for kinds in [json!([{ "name": "desert" }]), json!([{ "name": "dunes" }, { "name": "forest" }])] {
+            let changed = config.patched(&json!({ "world": { "biomes": { "kinds": kinds } } })).unwrap();
+            match config.live_changes(&changed) {
+                Err(ConfigUpdateError::RestartRequired(changes)) => {
+                    assert_eq!(changes[0].path, "world.biomes.kinds");
+                }
+                other => panic!("expected RestartRequired, got {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use super::validate::FieldError;
+use super::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+
+/// Time-varying values for numeric fields, keyed by field path such as
+/// `world.food_regen_rate`
+pub type ScheduleConfig = BTreeMap<String, ParameterSchedule>;
+
+/// A numeric config field as a function of tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParameterSchedule {
+    /// Linear interpolation between `[tick, value]` points, held at the first
+    /// value before the first point and the last value after the last
+    Piecewise { points: Vec<(u64, f64)> },
+    /// Repeats every `period` ticks, moving between `low` and `high`
+    Periodic {
+        period: u64,
+        low: f64,
+        high: f64,
+        #[serde(default)]
+        shape: Waveform,
+        /// How far into the cycle tick 0 is
+        #[serde(default)]
+        offset: u64,
+        /// Fraction of each cycle a square wave spends at `high`
+        #[serde(default = "default_duty")]
+        duty: f64,
+    },
+}
+
+fn default_duty() -> f64 {
+    0.5
+}
+
+/// Shape of a periodic schedule. Every shape starts its cycle at `low`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Waveform {
+    /// Smooth rise to `high` at mid-cycle and back
+    #[default]
+    Sine,
+    /// Linear rise to `high` at mid-cycle and back
+    Triangle,
+    /// Linear rise to `high`, then a drop back to `low`
+    Sawtooth,
+    /// `high` for the first `duty` of each cycle, `low` for the rest
+    Square,
+}
+
+impl ParameterSchedule {
+    pub fn value_at(&self, tick: u64) -> f64 {
+        match self {
+            ParameterSchedule::Piecewise { points } => {
+                let after = points.partition_point(|(point_tick, _)| *point_tick <= tick);
+                match (after.checked_sub(1).map(|i| points[i]), points.get(after)) {
+                    (Some((t0, v0)), Some(&(t1, v1))) => {
+                        let fraction = (tick - t0) as f64 / (t1 - t0) as f64;
+                        v0 + (v1 - v0) * fraction
+                    }
+                    (Some((_, value)), None) | (None, Some(&(_, value))) => value,
+                    (None, None) => 0.0,
+                }
+            }
+            ParameterSchedule::Periodic {
+                period,
+                low,
+                high,
+                shape,
+                offset,
+                duty,
+            } => {
+                let period = (*period).max(1);
+                let phase = ((tick % period + offset % period) % period) as f64 / period as f64;
+                let level = match shape {
+                    Waveform::Sine => (1.0 - (TAU * phase).cos()) / 2.0,
+                    Waveform::Triangle => 1.0 - (2.0 * phase - 1.0).abs(),
+                    Waveform::Sawtooth => phase,
+                    Waveform::Square => {
+                        if phase < *duty {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    }
+                };
+                low + (high - low) * level
+            }
+        }
+    }
+
+    /// Smallest and largest values the schedule ever takes
+    pub fn range(&self) -> (f64, f64) {
+        match self {
+            ParameterSchedule::Piecewise { points } => points
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (_, v)| (min.min(*v), max.max(*v))),
+            ParameterSchedule::Periodic { low, high, .. } => (low.min(*high), low.max(*high)),
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        match self {
+            ParameterSchedule::Piecewise { points } => {
+                if points.is_empty() {
+                    return Err("needs at least one point".to_string());
+                }
+                if points.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+                    return Err("point ticks must be strictly increasing".to_string());
+                }
+                if points.iter().any(|(_, v)| !v.is_finite()) {
+                    return Err("point values must be finite".to_string());
+                }
+            }
+            ParameterSchedule::Periodic {
+                period, low, high, duty, ..
+            } => {
+                if *period == 0 {
+                    return Err("period must be greater than 0".to_string());
+                }
+                if !low.is_finite() || !high.is_finite() {
+                    return Err("low and high must be finite".to_string());
+                }
+                if !(0.0..=1.0).contains(duty) {
+                    return Err(format!("duty must be between 0 and 1, got {}", duty));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mutable access to a numeric config field of any width
+pub enum NumericField<'a> {
+    Float(&'a mut f64),
+    Ticks(&'a mut u64),
+    Count(&'a mut usize),
+    Amount(&'a mut u32),
+}
+
+impl NumericField<'_> {
+    /// Integer fields are rounded and clamped at 0
+    pub fn set(self, value: f64) {
+        match self {
+            NumericField::Float(field) => *field = value,
+            NumericField::Ticks(field) => *field = value.round().max(0.0) as u64,
+            NumericField::Count(field) => *field = value.round().max(0.0) as usize,
+            NumericField::Amount(field) => *field = value.round().max(0.0) as u32,
+        }
+    }
+}
+
+impl Config {
+    /// The numeric fields a schedule can drive. World size and network
+    /// dimensions are left out since they can't change while running.
+    pub fn numeric_field_mut(&mut self, path: &str) -> Option<NumericField<'_>> {
+        use NumericField::*;
+        let world = &mut self.world;
+        let creature = &mut self.creature;
+        let combat = &mut self.combat;
+
+        Some(match path {
+            "world.initial_food_density" => Float(&mut world.initial_food_density),
+            "world.food_regen_rate" => Float(&mut world.food_regen_rate),
+            "world.max_food_per_cell" => Amount(&mut world.max_food_per_cell),
+            "world.plant_decay_ticks" => Amount(&mut world.plant_decay_ticks),
+            "world.meat_decay_ticks" => Amount(&mut world.meat_decay_ticks),
+            "world.plants.growth_rate" => Float(&mut world.plants.growth_rate),
+            "world.plants.seed_rate" => Float(&mut world.plants.seed_rate),
+            "creature.initial_population" => Count(&mut creature.initial_population),
+            "creature.max_population" => Count(&mut creature.max_population),
+            "creature.initial_energy" => Float(&mut creature.initial_energy),
+            "creature.max_energy" => Float(&mut creature.max_energy),
+            "creature.energy_per_food" => Float(&mut creature.energy_per_food),
+            "creature.energy_per_meat" => Float(&mut creature.energy_per_meat),
+            "creature.off_diet_efficiency" => Float(&mut creature.off_diet_efficiency),
+            "creature.energy_cost_per_tick" => Float(&mut creature.energy_cost_per_tick),
+            "creature.energy_cost_move" => Float(&mut creature.energy_cost_move),
+            "creature.energy_cost_reproduce" => Float(&mut creature.energy_cost_reproduce),
+            "creature.min_reproduce_energy" => Float(&mut creature.min_reproduce_energy),
+            "creature.reproduce_cooldown_ticks" => Ticks(&mut creature.reproduce_cooldown_ticks),
+            "creature.max_age_ticks" => Ticks(&mut creature.max_age_ticks),
+            "creature.energy_cost_sprint" => Float(&mut creature.energy_cost_sprint),
+            "creature.energy_share_amount" => Float(&mut creature.energy_share_amount),
+            "creature.rest_energy_multiplier" => Float(&mut creature.rest_energy_multiplier),
+            "creature.rest_healing_multiplier" => Float(&mut creature.rest_healing_multiplier),
+            "evolution.mutation_rate" => Float(&mut self.evolution.mutation_rate),
+            "combat.damage_per_attack" => Float(&mut combat.damage_per_attack),
+            "combat.damage_per_strong_attack" => Float(&mut combat.damage_per_strong_attack),
+            "combat.health_regen_rate" => Float(&mut combat.health_regen_rate),
+            "combat.health_regen_energy_cost" => Float(&mut combat.health_regen_energy_cost),
+            _ => return None,
+        })
+    }
+
+    /// Evaluate every schedule at `tick`, returning the effective config and
+    /// the scheduled values, or `None` when nothing is scheduled
+    pub fn scheduled_at(&self, tick: u64) -> Option<(Config, BTreeMap<String, f64>)> {
+        if self.schedule.is_empty() {
+            return None;
+        }
+
+        let mut config = self.clone();
+        let mut values = BTreeMap::new();
+        for (path, schedule) in &self.schedule {
+            let value = schedule.value_at(tick);
+            if let Some(field) = config.numeric_field_mut(path) {
+                field.set(value);
+                values.insert(path.clone(), value);
+            }
+        }
+        Some((config, values))
+    }
+
+    /// Check each schedule is well formed, targets a schedulable field, and
+    /// keeps its field valid at both ends of its range
+    pub(super) fn validate_schedule(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        for (path, schedule) in &self.schedule {
+            let schedule_path = format!("schedule.{}", path);
+            let error = |message: String| FieldError {
+                path: schedule_path.clone(),
+                message,
+            };
+
+            if self.clone().numeric_field_mut(path).is_none() {
+                errors.push(error(format!("{} is not a numeric field that can be scheduled", path)));
+                continue;
+            }
+            if let Err(message) = schedule.check() {
+                errors.push(error(message));
+                continue;
+            }
+
+            let (min, max) = schedule.range();
+            for value in [min, max] {
+                let mut config = self.clone();
+                config.schedule.clear();
+                if let Some(field) = config.numeric_field_mut(path) {
+                    field.set(value);
+                }
+                if let Err(invalid) = config.validate() {
+                    for field_error in invalid.0.into_iter().filter(|e| &e.path == path) {
+                        errors.push(error(format!("reaches {}, but {}", value, field_error.message)));
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piecewise_interpolates_and_holds() {
+        let schedule = ParameterSchedule::Piecewise {
+            points: vec![(100, 0.002), (1100, 0.001), (2000, 0.003)],
+        };
+        assert_eq!(schedule.value_at(0), 0.002);
+        assert_eq!(schedule.value_at(100), 0.002);
+        assert!((schedule.value_at(600) - 0.0015).abs() < 1e-12);
+        assert_eq!(schedule.value_at(1100), 0.001);
+        assert_eq!(schedule.value_at(50_000), 0.003);
+        assert_eq!(schedule.range(), (0.001, 0.003));
+    }
+
+    #[test]
+    fn test_periodic_shapes() {
+        let periodic = |shape| ParameterSchedule::Periodic {
+            period: 100,
+            low: 1.0,
+            high: 3.0,
+            shape,
+            offset: 0,
+            duty: 0.1,
+        };
+
+        let sine = periodic(Waveform::Sine);
+        assert!((sine.value_at(0) - 1.0).abs() < 1e-12);
+        assert!((sine.value_at(50) - 3.0).abs() < 1e-12);
+        assert!((sine.value_at(150) - 3.0).abs() < 1e-12);
+
+        assert_eq!(periodic(Waveform::Triangle).value_at(25), 2.0);
+        assert_eq!(periodic(Waveform::Sawtooth).value_at(75), 2.5);
+
+        let square = periodic(Waveform::Square);
+        assert_eq!(square.value_at(205), 3.0);
+        assert_eq!(square.value_at(215), 1.0);
+    }
+
+    #[test]
+    fn test_scheduled_config_and_validation() {
+        let mut config = Config::default();
+        let json = r#"{
+            "world.food_regen_rate": { "type": "piecewise", "points": [[0, 0.002], [100000, 0.0005]] },
+            "evolution.mutation_rate": { "type": "periodic", "shape": "square", "period": 20000, "low": 0.01, "high": 0.1, "duty": 0.05 },
+            "world.max_food_per_cell": { "type": "piecewise", "points": [[0, 10], [10, 5]] }
+        }"#;
+        config.schedule = serde_json::from_str(json).unwrap();
+        assert_eq!(config.validate(), Ok(()));
+
+        let (effective, values) = config.scheduled_at(50_000).unwrap();
+        assert!((effective.world.food_regen_rate - 0.00125).abs() < 1e-12);
+        assert_eq!(effective.evolution.mutation_rate, 0.01);
+        assert_eq!(effective.world.max_food_per_cell, 5);
+        assert_eq!(values.len(), 3);
+        assert_eq!(config.scheduled_at(40_500).unwrap().0.evolution.mutation_rate, 0.1);
+
+        config.schedule.insert(
+            "world.width".to_string(),
+            ParameterSchedule::Piecewise { points: vec![(0, 10.0)] },
+        );
+        config.schedule.insert(
+            "evolution.mutation_rate".to_string(),
+            ParameterSchedule::Piecewise {
+                points: vec![(0, 0.01), (10, 1.5)],
+            },
+        );
+        let errors = config.validate().unwrap_err();
+        let paths: Vec<&str> = errors.0.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["schedule.evolution.mutation_rate", "schedule.world.width"]);
+    }
+}
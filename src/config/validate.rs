@@ -0,0 +1,387 @@
+use super::{Config, Topology};
+use crate::world::{BiomeMap, TerrainMap, WorldMaps};
+use crate::creature::neural_net::{NeuralNetwork, OUTPUT_COUNT};
+use crate::simulation::tick::SENSOR_COUNT;
+use std::fmt;
+
+/// Highest rate the millisecond tick and update timers can represent
+const MAX_RATE_HZ: u64 = 1000;
+
+/// A config field that failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Dotted path of the field, e.g. `world.width`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Every problem `Config::validate` found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationErrors(pub Vec<FieldError>);
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} invalid config field(s)", self.0.len())?;
+        for error in &self.0 {
+            write!(f, "\n  {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+#[derive(Default)]
+struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    fn check(&mut self, valid: bool, path: &str, message: impl Into<String>) {
+        if !valid {
+            self.errors.push(FieldError {
+                path: path.to_string(),
+                message: message.into(),
+            });
+        }
+    }
+
+    fn non_negative(&mut self, path: &str, value: f64) {
+        self.check(value.is_finite() && value >= 0.0, path, format!("must be a finite number >= 0, got {}", value));
+    }
+
+    fn positive(&mut self, path: &str, value: f64) {
+        self.check(value.is_finite() && value > 0.0, path, format!("must be a finite number > 0, got {}", value));
+    }
+
+    fn fraction(&mut self, path: &str, value: f64) {
+        self.check((0.0..=1.0).contains(&value), path, format!("must be between 0 and 1, got {}", value));
+    }
+
+    fn rate(&mut self, path: &str, value: u64) {
+        self.check(
+            (1..=MAX_RATE_HZ).contains(&value),
+            path,
+            format!("must be between 1 and {}, got {}", MAX_RATE_HZ, value),
+        );
+    }
+}
+
+impl Config {
+    /// Check every field for values the simulation can't run with, reporting
+    /// all problems at once rather than stopping at the first.
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut v = Validator::default();
+
+        let world = &self.world;
+        v.check(world.width > 0, "world.width", "must be greater than 0");
+        v.check(world.height > 0, "world.height", "must be greater than 0");
+        if world.topology == Topology::Torus {
+            // Narrower, a cell's neighbours on either side would be the same
+            // cell or the cell itself
+            v.check(
+                world.width >= 3 && world.height >= 3,
+                "world.topology",
+                "torus needs a world of at least 3x3 cells",
+            );
+        }
+        v.fraction("world.initial_food_density", world.initial_food_density);
+        v.fraction("world.food_regen_rate", world.food_regen_rate);
+        v.check(world.max_food_per_cell > 0, "world.max_food_per_cell", "must be greater than 0");
+        v.non_negative("world.terrain.water_move_cost", world.terrain.water_move_cost);
+        v.non_negative("world.terrain.rough_move_cost", world.terrain.rough_move_cost);
+        let biomes = &world.biomes;
+        let mut names = std::collections::HashSet::new();
+        for (i, kind) in biomes.kinds.iter().enumerate() {
+            let path = |field: &str| format!("world.biomes.kinds.{}.{}", i, field);
+            v.check(!kind.name.is_empty(), &path("name"), "must not be empty");
+            v.check(
+                names.insert(kind.name.as_str()),
+                &path("name"),
+                format!("{:?} names another biome too", kind.name),
+            );
+            if let Some(density) = kind.initial_food_density {
+                v.fraction(&path("initial_food_density"), density);
+            }
+            if let Some(rate) = kind.food_regen_rate {
+                v.fraction(&path("food_regen_rate"), rate);
+            }
+            if let Some(max) = kind.max_food_per_cell {
+                v.check(max > 0, &path("max_food_per_cell"), "must be greater than 0");
+            }
+        }
+        v.positive("world.biomes.noise_scale", biomes.noise_scale);
+        v.check(
+            biomes.kinds.len() <= 256,
+            "world.biomes.kinds",
+            format!("at most 256 biomes are supported, got {}", biomes.kinds.len()),
+        );
+        v.check(
+            biomes.map.is_none() || !biomes.kinds.is_empty(),
+            "world.biomes.map",
+            "needs world.biomes.kinds to say what each biome is",
+        );
+
+        let climate = &world.climate;
+        v.fraction("world.climate.season_food_amplitude", climate.season_food_amplitude);
+        v.fraction("world.climate.season_metabolism_amplitude", climate.season_metabolism_amplitude);
+        v.fraction("world.climate.night_sensor_range", climate.night_sensor_range);
+        v.fraction("world.plants.growth_rate", world.plants.growth_rate);
+        v.fraction("world.plants.seed_rate", world.plants.seed_rate);
+
+        let creature = &self.creature;
+        v.check(
+            creature.initial_population > 0,
+            "creature.initial_population",
+            "must be greater than 0",
+        );
+        v.check(
+            creature.initial_population <= world.width.saturating_mul(world.height),
+            "creature.initial_population",
+            format!(
+                "{} creatures do not fit in a {}x{} world",
+                creature.initial_population, world.width, world.height
+            ),
+        );
+        v.check(
+            creature.max_population == 0 || creature.max_population >= creature.initial_population,
+            "creature.max_population",
+            format!(
+                "must be 0 (unlimited) or at least creature.initial_population ({})",
+                creature.initial_population
+            ),
+        );
+        v.positive("creature.initial_energy", creature.initial_energy);
+        v.positive("creature.max_energy", creature.max_energy);
+        v.check(
+            creature.initial_energy <= creature.max_energy,
+            "creature.initial_energy",
+            format!("must not exceed creature.max_energy ({})", creature.max_energy),
+        );
+        v.non_negative("creature.energy_per_food", creature.energy_per_food);
+        v.non_negative("creature.energy_per_meat", creature.energy_per_meat);
+        v.fraction("creature.off_diet_efficiency", creature.off_diet_efficiency);
+        v.non_negative("creature.energy_cost_per_tick", creature.energy_cost_per_tick);
+        v.non_negative("creature.energy_cost_move", creature.energy_cost_move);
+        v.non_negative("creature.energy_cost_reproduce", creature.energy_cost_reproduce);
+        v.non_negative("creature.min_reproduce_energy", creature.min_reproduce_energy);
+        v.check(
+            creature.min_reproduce_energy <= creature.max_energy,
+            "creature.min_reproduce_energy",
+            format!(
+                "must not exceed creature.max_energy ({}), or no creature can ever reproduce",
+                creature.max_energy
+            ),
+        );
+        v.non_negative("creature.energy_cost_sprint", creature.energy_cost_sprint);
+        v.non_negative("creature.energy_share_amount", creature.energy_share_amount);
+        v.non_negative("creature.rest_energy_multiplier", creature.rest_energy_multiplier);
+        v.non_negative("creature.rest_healing_multiplier", creature.rest_healing_multiplier);
+
+        let evolution = &self.evolution;
+        v.fraction("evolution.mutation_rate", evolution.mutation_rate);
+        // Fewer inputs than sensors is allowed, so configs written before a
+        // sensor was added keep working; their creatures just don't see it
+        v.check(
+            (1..=SENSOR_COUNT).contains(&evolution.neural_net_inputs),
+            "evolution.neural_net_inputs",
+            format!(
+                "must be between 1 and {} (the number of sensors), got {}",
+                SENSOR_COUNT, evolution.neural_net_inputs
+            ),
+        );
+        v.check(
+            evolution.neural_net_hidden > 0,
+            "evolution.neural_net_hidden",
+            "must be greater than 0",
+        );
+        v.check(
+            evolution.neural_net_outputs == OUTPUT_COUNT,
+            "evolution.neural_net_outputs",
+            format!(
+                "must be {} (one per action), got {}",
+                OUTPUT_COUNT, evolution.neural_net_outputs
+            ),
+        );
+        let weights = NeuralNetwork::weight_count(
+            evolution.neural_net_inputs,
+            evolution.neural_net_hidden,
+            evolution.neural_net_outputs,
+        );
+        v.check(
+            evolution.genome_size > weights,
+            "evolution.genome_size",
+            format!(
+                "must be at least {} (one gene per network weight plus the digestion gene), got {}",
+                weights + 1,
+                evolution.genome_size
+            ),
+        );
+
+        let combat = &self.combat;
+        v.non_negative("combat.damage_per_attack", combat.damage_per_attack);
+        v.non_negative("combat.damage_per_strong_attack", combat.damage_per_strong_attack);
+        v.non_negative("combat.health_regen_rate", combat.health_regen_rate);
+        v.non_negative("combat.health_regen_energy_cost", combat.health_regen_energy_cost);
+
+        v.rate("simulation.ticks_per_second", self.simulation.ticks_per_second);
+
+        let checkpoint = &self.checkpoint;
+        v.check(
+            checkpoint.interval_seconds > 0,
+            "checkpoint.interval_seconds",
+            "must be greater than 0",
+        );
+        v.check(
+            checkpoint.interval_ticks != Some(0),
+            "checkpoint.interval_ticks",
+            "must be greater than 0 or null",
+        );
+        v.check(
+            checkpoint.interval_generations != Some(0),
+            "checkpoint.interval_generations",
+            "must be greater than 0 or null",
+        );
+        v.check(
+            !checkpoint.directory.is_empty(),
+            "checkpoint.directory",
+            "must not be empty",
+        );
+        v.check(
+            checkpoint.keep_last_n > 0,
+            "checkpoint.keep_last_n",
+            "must be at least 1, or every checkpoint is deleted right after it is written",
+        );
+
+        v.check(
+            !self.server.address.is_empty(),
+            "server.address",
+            "must not be empty",
+        );
+        v.rate("server.update_rate_hz", self.server.update_rate_hz);
+
+        v.errors.extend(self.validate_schedule());
+
+        if v.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(v.errors))
+        }
+    }
+
+    /// Read the terrain and biome maps the config names and check they fit
+    /// it. This is the only check that reads files, so it is left out of
+    /// `validate` and done once, when a world is created.
+    pub fn load_maps(&self) -> Result<WorldMaps, ValidationErrors> {
+        let mut v = Validator::default();
+        let mut maps = WorldMaps::default();
+
+        let world = &self.world;
+        if let Some(path) = &world.terrain.map {
+            match TerrainMap::load(path) {
+                Ok(map) => {
+                    v.check(
+                        map.stretched(world.width, world.height).iter().any(|t| t.is_passable()),
+                        "world.terrain.map",
+                        format!("{} leaves no cell free of walls", path),
+                    );
+                    maps.terrain = Some(map);
+                }
+                Err(e) => v.check(false, "world.terrain.map", e),
+            }
+        }
+
+        let biomes = &world.biomes;
+        if let (Some(path), false) = (&biomes.map, biomes.kinds.is_empty()) {
+            match BiomeMap::load(path, biomes.kinds.len()) {
+                Ok(map) => {
+                    let highest = map.cells().iter().copied().max().unwrap_or(0) as usize;
+                    v.check(
+                        highest < biomes.kinds.len(),
+                        "world.biomes.map",
+                        format!("{} uses biome {} but only {} are defined", path, highest, biomes.kinds.len()),
+                    );
+                    maps.biomes = Some(map);
+                }
+                Err(e) => v.check(false, "world.biomes.map", e),
+            }
+        }
+
+        if v.errors.is_empty() {
+            Ok(maps)
+        } else {
+            Err(ValidationErrors(v.errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert_eq!(Config::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_reports_every_invalid_field() {
+        let mut config = Config::default();
+        config.simulation.ticks_per_second = 0;
+        config.server.update_rate_hz = 0;
+        config.evolution.neural_net_outputs = 10;
+        config.evolution.genome_size = 100;
+        config.world.initial_food_density = 1.5;
+
+        let errors = config.validate().unwrap_err();
+        let paths: Vec<&str> = errors.0.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "world.initial_food_density",
+                "evolution.neural_net_outputs",
+                "evolution.genome_size",
+                "simulation.ticks_per_second",
+                "server.update_rate_hz",
+            ]
+        );
+        assert!(errors.to_string().contains("evolution.genome_size: must be at least"));
+    }
+
+    #[test]
+    fn test_genome_needs_a_digestion_gene() {
+        let mut config = Config::default();
+        config.evolution.genome_size = NeuralNetwork::weight_count(
+            config.evolution.neural_net_inputs,
+            config.evolution.neural_net_hidden,
+            config.evolution.neural_net_outputs,
+        );
+
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+        assert_eq!(
+            errors.0[0].to_string(),
+            "evolution.genome_size: must be at least 369 (one gene per network weight plus the digestion gene), got 368"
+        );
+
+        config.evolution.genome_size += 1;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_map_files_are_only_read_by_load_maps() {
+        let mut config = Config::default();
+        config.world.terrain.map = Some("no_such_terrain_map.txt".to_string());
+
+        assert_eq!(config.validate(), Ok(()));
+        let errors = config.load_maps().unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+        assert_eq!(errors.0[0].path, "world.terrain.map");
+    }
+}
@@ -8,8 +8,7 @@ pub struct Genome {
 }
 
 impl Genome {
-    pub fn random(size: usize) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn random<R: Rng + ?Sized>(size: usize, rng: &mut R) -> Self {
         let genes = (0..size).map(|_| rng.gen()).collect();
         Self {
             genes,
@@ -17,8 +16,7 @@ impl Genome {
         }
     }
 
-    pub fn from_parent(parent: &Genome, mutation_rate: f64) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn from_parent<R: Rng + ?Sized>(parent: &Genome, mutation_rate: f64, rng: &mut R) -> Self {
         let mut genes = parent.genes.clone();
 
         for gene in &mut genes {
@@ -43,6 +41,16 @@ impl Genome {
             .collect()
     }
 
+    /// Diet from the gene at `index`: 0 digests only plants well, 1 only
+    /// meat. A genome too short to have that gene gives a generalist at 0.5.
+    pub fn digestion(&self, index: usize) -> f64 {
+        if index < self.genes.len() {
+            self.get_normalized(index)
+        } else {
+            0.5
+        }
+    }
+
     pub fn similarity(&self, other: &Genome) -> f64 {
         if self.genes.len() != other.genes.len() {
             return 0.0;
@@ -52,7 +60,7 @@ impl Genome {
             .iter()
             .zip(&other.genes)
             .map(|(a, b)| {
-                let diff = (*a as i32 - *b as i32).abs() as u32;
+                let diff = (*a as i32 - *b as i32).unsigned_abs();
                 if diff < 10 {
                     1
                 } else {
@@ -71,15 +79,15 @@ mod tests {
 
     #[test]
     fn test_random_genome() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         assert_eq!(genome.genes.len(), 100);
         assert_eq!(genome.generation, 0);
     }
 
     #[test]
     fn test_genome_from_parent() {
-        let parent = Genome::random(100);
-        let child = Genome::from_parent(&parent, 0.1);
+        let parent = Genome::random(100, &mut rand::thread_rng());
+        let child = Genome::from_parent(&parent, 0.1, &mut rand::thread_rng());
 
         assert_eq!(child.genes.len(), parent.genes.len());
         assert_eq!(child.generation, parent.generation + 1);
@@ -117,6 +125,16 @@ mod tests {
         assert!(trait_values[0] > 0.0 && trait_values[0] < 1.0);
     }
 
+    #[test]
+    fn test_digestion() {
+        let genome = Genome {
+            genes: vec![10, 20, 255],
+            generation: 0,
+        };
+        assert_eq!(genome.digestion(2), 1.0);
+        assert_eq!(genome.digestion(3), 0.5);
+    }
+
     #[test]
     fn test_similarity() {
         let genome1 = Genome {
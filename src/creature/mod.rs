@@ -6,6 +6,7 @@ pub mod reproduction;
 use genome::Genome;
 use metabolism::Metabolism;
 use neural_net::{Action, NeuralNetwork};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,8 +54,8 @@ impl Creature {
         self.metabolism.is_alive()
     }
 
-    pub fn decide_action(&self, inputs: &[f64]) -> Action {
-        self.brain.decide_action(inputs)
+    pub fn decide_action<R: Rng + ?Sized>(&self, inputs: &[f64], rng: &mut R) -> Action {
+        self.brain.decide_action(inputs, rng)
     }
 
     pub fn consume_energy(&mut self, amount: f64) -> bool {
@@ -85,6 +86,23 @@ impl Creature {
     pub fn increment_offspring(&mut self) {
         self.offspring_count += 1;
     }
+
+    /// Diet trait, from the first gene after the brain's weights so it never
+    /// doubles as a weight
+    pub fn digestion(&self) -> f64 {
+        let (inputs, hidden, outputs) = self.brain.dimensions();
+        self.genome.digestion(NeuralNetwork::weight_count(inputs, hidden, outputs))
+    }
+
+    /// Fraction of a food's energy this creature digests. Plant and meat
+    /// efficiency trade off through the genome's digestion trait, and a
+    /// complete specialist still gets `off_diet_efficiency` from the other
+    /// food.
+    pub fn digestive_efficiency(&self, is_meat: bool, off_diet_efficiency: f64) -> f64 {
+        let digestion = self.digestion();
+        let affinity = if is_meat { digestion } else { 1.0 - digestion };
+        off_diet_efficiency + (1.0 - off_diet_efficiency) * affinity
+    }
 }
 
 #[cfg(test)]
@@ -93,7 +111,7 @@ mod tests {
 
     #[test]
     fn test_creature_creation() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let creature = Creature::new(1, 10, 20, genome, 100.0, 200.0, (8, 6, 4));
 
         assert_eq!(creature.id, 1);
@@ -105,7 +123,7 @@ mod tests {
 
     #[test]
     fn test_creature_energy() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let mut creature = Creature::new(1, 10, 20, genome, 100.0, 200.0, (8, 6, 4));
 
         creature.consume_energy(30.0);
@@ -126,11 +144,11 @@ mod tests {
 
     #[test]
     fn test_creature_decide_action() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let creature = Creature::new(1, 10, 20, genome, 100.0, 200.0, (8, 6, 4));
 
         let inputs = vec![0.5, 0.3, 0.1, 0.9, 0.2, 0.7, 0.4, 0.6];
-        let action = creature.decide_action(&inputs);
+        let action = creature.decide_action(&inputs, &mut rand::thread_rng());
 
         assert!(matches!(
             action,
@@ -138,9 +156,21 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_digestive_efficiency() {
+        // A (8, 6, 4) brain has 72 weights, so the digestion gene is the 73rd
+        let mut genome = Genome::random(100, &mut rand::thread_rng());
+        genome.genes[72] = 0;
+        let herbivore = Creature::new(1, 10, 20, genome, 100.0, 200.0, (8, 6, 4));
+
+        assert_eq!(herbivore.digestive_efficiency(false, 0.2), 1.0);
+        assert_eq!(herbivore.digestive_efficiency(true, 0.2), 0.2);
+        assert_eq!(herbivore.digestive_efficiency(true, 1.0), 1.0);
+    }
+
     #[test]
     fn test_creature_can_reproduce() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let creature = Creature::new(1, 10, 20, genome, 150.0, 200.0, (8, 6, 4));
 
         assert!(creature.can_reproduce(100.0, 1000, 100));
@@ -2,6 +2,9 @@ use super::genome::Genome;
 use serde::{Deserialize, Serialize};
 use rand::Rng;
 
+/// Number of network outputs, one per action `decide_action` can choose
+pub const OUTPUT_COUNT: usize = 12;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NeuralNetwork {
     input_size: usize,
@@ -16,9 +19,9 @@ impl NeuralNetwork {
         let mut idx = 0;
 
         let mut weights_ih = vec![vec![0.0; input_size]; hidden_size];
-        for h in 0..hidden_size {
-            for i in 0..input_size {
-                weights_ih[h][i] = genome.get_normalized(idx) * 2.0 - 1.0;
+        for row in weights_ih.iter_mut() {
+            for weight in row.iter_mut() {
+                *weight = genome.get_normalized(idx) * 2.0 - 1.0;
                 idx += 1;
                 if idx >= genome.genes.len() {
                     idx = 0;
@@ -27,9 +30,9 @@ impl NeuralNetwork {
         }
 
         let mut weights_ho = vec![vec![0.0; hidden_size]; output_size];
-        for o in 0..output_size {
-            for h in 0..hidden_size {
-                weights_ho[o][h] = genome.get_normalized(idx) * 2.0 - 1.0;
+        for row in weights_ho.iter_mut() {
+            for weight in row.iter_mut() {
+                *weight = genome.get_normalized(idx) * 2.0 - 1.0;
                 idx += 1;
                 if idx >= genome.genes.len() {
                     idx = 0;
@@ -46,6 +49,16 @@ impl NeuralNetwork {
         }
     }
 
+    /// Number of genes needed to give every weight its own gene
+    pub fn weight_count(input_size: usize, hidden_size: usize, output_size: usize) -> usize {
+        input_size * hidden_size + hidden_size * output_size
+    }
+
+    /// Network dimensions as (inputs, hidden, outputs)
+    pub fn dimensions(&self) -> (usize, usize, usize) {
+        (self.input_size, self.hidden_size, self.output_size)
+    }
+
     pub fn forward(&self, inputs: &[f64]) -> Vec<f64> {
         assert_eq!(inputs.len(), self.input_size, "Input size mismatch");
 
@@ -72,7 +85,7 @@ impl NeuralNetwork {
         x.tanh()
     }
 
-    pub fn decide_action(&self, inputs: &[f64]) -> Action {
+    pub fn decide_action<R: Rng + ?Sized>(&self, inputs: &[f64], rng: &mut R) -> Action {
         let outputs = self.forward(inputs);
 
         // Compute softmax probabilities
@@ -82,7 +95,6 @@ impl NeuralNetwork {
         let probabilities: Vec<f64> = exp_outputs.iter().map(|&x| x / sum_exp).collect();
 
         // Sample action based on probabilities
-        let mut rng = rand::thread_rng();
         let random_value: f64 = rng.gen(); // 0.0 to 1.0
 
         let mut cumulative = 0.0;
@@ -181,7 +193,7 @@ mod tests {
 
     #[test]
     fn test_neural_network_creation() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let nn = NeuralNetwork::from_genome(&genome, 8, 6, 4);
 
         assert_eq!(nn.input_size, 8);
@@ -191,7 +203,7 @@ mod tests {
 
     #[test]
     fn test_neural_network_forward() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let nn = NeuralNetwork::from_genome(&genome, 8, 6, 4);
 
         let inputs = vec![0.5, 0.3, 0.1, 0.9, 0.2, 0.7, 0.4, 0.6];
@@ -205,11 +217,11 @@ mod tests {
 
     #[test]
     fn test_decide_action() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let nn = NeuralNetwork::from_genome(&genome, 8, 6, 12);
 
         let inputs = vec![0.5, 0.3, 0.1, 0.9, 0.2, 0.7, 0.4, 0.6];
-        let action = nn.decide_action(&inputs);
+        let action = nn.decide_action(&inputs, &mut rand::thread_rng());
 
         assert!(matches!(
             action,
@@ -1,8 +1,10 @@
 use super::Creature;
 use super::genome::Genome;
+use rand::Rng;
 
 impl Creature {
-    pub fn reproduce(
+    #[allow(clippy::too_many_arguments)]
+    pub fn reproduce<R: Rng + ?Sized>(
         &mut self,
         offspring_id: u64,
         target_x: usize,
@@ -11,8 +13,8 @@ impl Creature {
         energy_cost: f64,
         initial_energy: f64,
         max_energy: f64,
-        nn_config: (usize, usize, usize),
         current_tick: u64,
+        rng: &mut R,
     ) -> Option<Creature> {
         if !self.metabolism.can_afford(energy_cost) {
             return None;
@@ -21,7 +23,7 @@ impl Creature {
         self.consume_energy(energy_cost);
         self.last_reproduce_tick = current_tick;
 
-        let offspring_genome = Genome::from_parent(&self.genome, mutation_rate);
+        let offspring_genome = Genome::from_parent(&self.genome, mutation_rate, rng);
 
         Some(Creature::new(
             offspring_id,
@@ -30,7 +32,10 @@ impl Creature {
             offspring_genome,
             initial_energy,
             max_energy,
-            nn_config,
+            // The offspring's brain has the same shape as its parent's, which
+            // may differ from the config in a population resumed from an
+            // older checkpoint
+            self.brain.dimensions(),
         ))
     }
 }
@@ -41,10 +46,11 @@ mod tests {
 
     #[test]
     fn test_reproduce() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let mut parent = Creature::new(1, 10, 20, genome, 150.0, 200.0, (8, 6, 4));
 
-        let offspring = parent.reproduce(2, 11, 20, 0.01, 50.0, 100.0, 200.0, (8, 6, 4), 100);
+        let mut rng = rand::thread_rng();
+        let offspring = parent.reproduce(2, 11, 20, 0.01, 50.0, 100.0, 200.0, 100, &mut rng);
 
         assert!(offspring.is_some());
         let child = offspring.unwrap();
@@ -58,10 +64,11 @@ mod tests {
 
     #[test]
     fn test_reproduce_insufficient_energy() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let mut parent = Creature::new(1, 10, 20, genome, 40.0, 200.0, (8, 6, 4));
 
-        let offspring = parent.reproduce(2, 11, 20, 0.01, 50.0, 100.0, 200.0, (8, 6, 4), 100);
+        let mut rng = rand::thread_rng();
+        let offspring = parent.reproduce(2, 11, 20, 0.01, 50.0, 100.0, 200.0, 100, &mut rng);
 
         assert!(offspring.is_none());
         assert_eq!(parent.energy(), 40.0);
@@ -69,10 +76,11 @@ mod tests {
 
     #[test]
     fn test_offspring_has_mutations() {
-        let genome = Genome::random(100);
+        let genome = Genome::random(100, &mut rand::thread_rng());
         let mut parent = Creature::new(1, 10, 20, genome.clone(), 150.0, 200.0, (8, 6, 4));
 
-        let offspring = parent.reproduce(2, 11, 20, 0.5, 50.0, 100.0, 200.0, (8, 6, 4), 100);
+        let mut rng = rand::thread_rng();
+        let offspring = parent.reproduce(2, 11, 20, 0.5, 50.0, 100.0, 200.0, 100, &mut rng);
 
         assert!(offspring.is_some());
         let child = offspring.unwrap();
@@ -0,0 +1,233 @@
+pub mod replay;
+
+use crate::config::Config;
+use crate::simulation::events::SimEvent;
+use bincode::Options;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"EVOJ";
+const FORMAT_VERSION: u16 = 1;
+const EXTENSION: &str = "evj";
+
+/// Describes the state a journal segment starts from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalHeader {
+    pub seed: u64,
+    pub start_tick: u64,
+    /// Config the segment was recorded with, as JSON
+    pub config_json: String,
+}
+
+impl JournalHeader {
+    pub fn new(seed: u64, start_tick: u64, config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            seed,
+            start_tick,
+            config_json: serde_json::to_string(config)?,
+        })
+    }
+
+    pub fn config(&self) -> Result<Config, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(&self.config_json)?)
+    }
+}
+
+/// All events emitted while processing one tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TickRecord {
+    pub tick: u64,
+    pub events: Vec<SimEvent>,
+}
+
+fn codec() -> impl Options {
+    // Varint integers keep ids and coordinates to a byte or two each
+    bincode::DefaultOptions::new()
+}
+
+/// Appends tick records to a journal segment.
+///
+/// Each record is a little-endian `u32` length followed by the bincode body,
+/// so a reader can stop cleanly at a record cut short by a crash.
+pub struct JournalWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl JournalWriter {
+    /// Start a new journal segment in `directory`
+    pub fn create(directory: &str, header: &JournalHeader) -> Result<Self, Box<dyn std::error::Error>> {
+        let dir = Path::new(directory);
+        if !dir.exists() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let path = create_journal_path(directory, header.start_tick);
+        let file = OpenOptions::new().create_new(true).append(true).open(&path)?;
+        let mut writer = BufWriter::new(file);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        write_frame(&mut writer, &codec().serialize(header)?)?;
+        writer.flush()?;
+
+        Ok(Self { path, writer })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&mut self, tick: u64, events: Vec<SimEvent>) -> Result<(), Box<dyn std::error::Error>> {
+        let record = TickRecord { tick, events };
+        write_frame(&mut self.writer, &codec().serialize(&record)?)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads tick records back from a journal segment
+pub struct JournalReader {
+    header: JournalHeader,
+    reader: BufReader<File>,
+}
+
+impl JournalReader {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(format!("{} is not an event journal", path.display()).into());
+        }
+
+        let mut version = [0u8; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(format!("Unsupported journal format version {}", version).into());
+        }
+
+        let header_bytes = read_frame(&mut reader)?.ok_or("Journal header is truncated")?;
+        let header = codec().deserialize(&header_bytes)?;
+
+        Ok(Self { header, reader })
+    }
+
+    pub fn header(&self) -> &JournalHeader {
+        &self.header
+    }
+
+    /// Returns the next record, or `None` at the end of the journal.
+    /// A record truncated by a crash is treated as the end.
+    pub fn next_record(&mut self) -> Result<Option<TickRecord>, Box<dyn std::error::Error>> {
+        match read_frame(&mut self.reader)? {
+            Some(bytes) => Ok(Some(codec().deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn write_frame(writer: &mut impl Write, bytes: &[u8]) -> std::io::Result<()> {
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+fn read_frame(reader: &mut impl Read) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    if let Err(e) = reader.read_exact(&mut len) {
+        return if e.kind() == ErrorKind::UnexpectedEof { Ok(None) } else { Err(e) };
+    }
+
+    let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Ok(Some(bytes)),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+            log::warn!("Journal ends with a truncated record, ignoring it");
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub fn create_journal_path(directory: &str, start_tick: u64) -> PathBuf {
+    let filename = format!(
+        "journal_{:012}_{}.{}",
+        start_tick,
+        Utc::now().format("%Y-%m-%d_%H-%M-%S"),
+        EXTENSION
+    );
+    Path::new(directory).join(filename)
+}
+
+/// Find the most recent journal segment that started at or before `tick`
+pub fn find_journal_for_tick(directory: &str, tick: u64) -> Option<PathBuf> {
+    let entries = fs::read_dir(directory).ok()?;
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some(EXTENSION))
+        .filter_map(|path| {
+            let start_tick = path
+                .file_stem()?
+                .to_str()?
+                .strip_prefix("journal_")?
+                .split('_')
+                .next()?
+                .parse::<u64>()
+                .ok()?;
+            Some((start_tick, path))
+        })
+        .filter(|(start_tick, _)| *start_tick <= tick)
+        .max()
+        .map(|(_, path)| path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_round_trip() {
+        let directory = "test_journal_round_trip_temp";
+        let config = Config::default();
+        let header = JournalHeader::new(7, 0, &config).unwrap();
+
+        let events = vec![
+            SimEvent::Move { id: 3, x: 10, y: 11 },
+            SimEvent::Birth {
+                id: 9,
+                parent_id: 3,
+                x: 10,
+                y: 12,
+                generation: 1,
+            },
+        ];
+
+        let mut writer = JournalWriter::create(directory, &header).unwrap();
+        writer.append(0, events.clone()).unwrap();
+        writer.append(1, Vec::new()).unwrap();
+        writer.flush().unwrap();
+        let path = writer.path().to_path_buf();
+        drop(writer);
+
+        let mut reader = JournalReader::open(&path).unwrap();
+        assert_eq!(reader.header().seed, 7);
+        assert_eq!(reader.next_record().unwrap(), Some(TickRecord { tick: 0, events }));
+        assert_eq!(reader.next_record().unwrap().unwrap().tick, 1);
+        assert_eq!(reader.next_record().unwrap(), None);
+
+        assert_eq!(find_journal_for_tick(directory, 5), Some(path));
+
+        let _ = fs::remove_dir_all(directory);
+    }
+}
@@ -0,0 +1,174 @@
+use super::{JournalReader, TickRecord};
+use crate::checkpoint;
+use crate::config::Config;
+use crate::simulation::events::SimEvent;
+use crate::simulation::SimulationState;
+use std::path::Path;
+
+/// Re-drives a simulation from its starting state and checks every tick
+/// against the recorded journal.
+///
+/// Replays are exact because the simulation is seeded: ticking the same state
+/// with the same config reproduces the recorded events, and the first tick
+/// where they differ is reported as a divergence.
+pub struct Replay {
+    state: SimulationState,
+    config: Config,
+    reader: JournalReader,
+}
+
+/// Result of replaying a single tick
+#[derive(Debug, Clone)]
+pub struct ReplayStep {
+    pub tick: u64,
+    pub events: Vec<SimEvent>,
+}
+
+impl Replay {
+    /// Start a replay of `journal_path`, either from the checkpoint it was
+    /// resumed from or, without one, from a fresh world built from its seed.
+    pub fn open(
+        journal_path: &Path,
+        checkpoint_path: Option<&Path>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let reader = JournalReader::open(journal_path)?;
+        let header = reader.header().clone();
+        let mut config = header.config()?;
+        config.simulation.seed = Some(header.seed);
+
+        let state = match checkpoint_path {
+            Some(path) => {
+                let mut state = checkpoint::load_checkpoint_from(path)?;
+                state.prepare_resume(&config);
+                state
+            }
+            None => SimulationState::new(&config),
+        };
+
+        if state.seed != header.seed {
+            return Err(format!(
+                "Checkpoint seed {} does not match journal seed {}",
+                state.seed, header.seed
+            )
+            .into());
+        }
+        if state.tick < header.start_tick {
+            return Err(format!(
+                "Starting state is at tick {} but the journal starts at tick {}",
+                state.tick, header.start_tick
+            )
+            .into());
+        }
+
+        Ok(Self::new(state, config, reader))
+    }
+
+    pub fn new(mut state: SimulationState, config: Config, reader: JournalReader) -> Self {
+        state.events.set_enabled(true);
+        Self {
+            state,
+            config,
+            reader,
+        }
+    }
+
+    pub fn state(&self) -> &SimulationState {
+        &self.state
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Replay the next tick. Returns `None` once the journal is exhausted.
+    pub fn step(&mut self) -> Result<Option<ReplayStep>, Box<dyn std::error::Error>> {
+        let Some(record) = self.next_record_for(self.state.tick)? else {
+            return Ok(None);
+        };
+
+        let tick = self.state.tick;
+        self.state.tick(&self.config);
+        let events = self.state.events.take();
+
+        if events != record.events {
+            let position = events
+                .iter()
+                .zip(&record.events)
+                .position(|(replayed, recorded)| replayed != recorded)
+                .unwrap_or(events.len().min(record.events.len()));
+            return Err(format!(
+                "Replay diverged from the journal at tick {} (event {}: replayed {:?}, recorded {:?})",
+                tick,
+                position,
+                events.get(position),
+                record.events.get(position)
+            )
+            .into());
+        }
+
+        Ok(Some(ReplayStep { tick, events }))
+    }
+
+    /// Replay forward until the state reaches `tick`.
+    /// Returns false if the journal ends first.
+    pub fn seek(&mut self, tick: u64) -> Result<bool, Box<dyn std::error::Error>> {
+        while self.state.tick < tick {
+            if self.step()?.is_none() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn next_record_for(&mut self, tick: u64) -> Result<Option<TickRecord>, Box<dyn std::error::Error>> {
+        while let Some(record) = self.reader.next_record()? {
+            if record.tick < tick {
+                continue;
+            }
+            if record.tick > tick {
+                return Err(format!("Journal has no record for tick {}", tick).into());
+            }
+            return Ok(Some(record));
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::{JournalHeader, JournalWriter};
+
+    #[test]
+    fn test_replay_matches_recorded_run() {
+        let directory = "test_replay_temp";
+        let mut config = Config::default();
+        config.world.width = 30;
+        config.world.height = 30;
+        config.creature.initial_population = 40;
+        config.simulation.seed = Some(99);
+
+        let mut sim = SimulationState::new(&config);
+        sim.events.set_enabled(true);
+        let header = JournalHeader::new(sim.seed, sim.tick, &config).unwrap();
+        let mut writer = JournalWriter::create(directory, &header).unwrap();
+        for _ in 0..20 {
+            let tick = sim.tick;
+            sim.tick(&config);
+            writer.append(tick, sim.events.take()).unwrap();
+        }
+        writer.flush().unwrap();
+        let path = writer.path().to_path_buf();
+        drop(writer);
+
+        let mut replay = Replay::open(&path, None).unwrap();
+        assert!(replay.seek(10).unwrap());
+        assert_eq!(replay.state().tick, 10);
+        assert!(replay.seek(20).unwrap());
+        assert!(replay.step().unwrap().is_none());
+        assert_eq!(replay.state().creatures.len(), sim.creatures.len());
+        assert_eq!(replay.state().total_births, sim.total_births);
+
+        let _ = std::fs::remove_dir_all(directory);
+    }
+}
@@ -2,7 +2,9 @@ pub mod checkpoint;
 pub mod config;
 pub mod creature;
 pub mod evolution;
+pub mod journal;
 pub mod server;
+pub mod shutdown;
 pub mod simulation;
 pub mod stats;
 pub mod world;
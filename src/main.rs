@@ -1,24 +1,167 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use evo_server::checkpoint;
+use evo_server::checkpoint::inspect::InspectReport;
+use evo_server::config::overrides::ConfigOverride;
 use evo_server::config::Config;
+use evo_server::journal::replay::Replay;
 use evo_server::server;
+use evo_server::shutdown::{Shutdown, ShutdownReason};
+use evo_server::simulation::batch::{run_batch, BatchOptions};
+use evo_server::simulation::control::{CommandSender, SimulationCommand};
+use evo_server::simulation::events::SimEvent;
+use evo_server::simulation::registry::{WorldRegistry, DEFAULT_WORLD};
+use evo_server::simulation::sweep::{self, SweepAxis};
 use evo_server::simulation::SimulationState;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tokio::time::{interval, Duration, Instant};
+use evo_server::stats::export::{MetricsFormat, MetricsWriter};
+use evo_server::world::WorldMaps;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::oneshot;
+use tokio::time::{interval, Duration};
+
+/// How often the config file is checked for changes
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Parser, Debug)]
 #[command(name = "evo-server")]
 #[command(about = "Evolution Simulator Server", long_about = None)]
 struct Args {
-    #[arg(short, long, default_value = "config.json")]
+    #[arg(short, long, default_value = "config.json", global = true)]
     config: String,
 
-    #[arg(long)]
+    #[arg(long, global = true)]
     no_checkpoint: bool,
 
     #[arg(long)]
     no_server: bool,
+
+    /// Rewrite the config file with missing fields filled in from defaults,
+    /// keeping the original as a timestamped backup
+    #[arg(long)]
+    update_config: bool,
+
+    /// Override a config field, e.g. `--set world.width=500`. Applied after
+    /// the file and EVO_* environment variables; may be repeated
+    #[arg(long = "set", value_name = "PATH=VALUE", value_parser = ConfigOverride::from_assignment, global = true)]
+    overrides: Vec<ConfigOverride>,
+
+    /// Print the effective config, after every override, and exit
+    #[arg(long)]
+    print_config: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run an experiment without pacing or a server
+    Run(RunArgs),
+    /// Run every combination of swept field values and seeds headlessly, in parallel
+    Sweep(SweepArgs),
+    /// Replay an event journal tick by tick
+    Replay(ReplayArgs),
+    /// Export a checkpoint of either format as JSON
+    Export {
+        /// Checkpoint to export
+        checkpoint: PathBuf,
+
+        /// Output path (defaults to <checkpoint name>.export.json in the
+        /// current directory, outside the checkpoint directory so it isn't
+        /// taken for a checkpoint)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Summarize a checkpoint without starting the simulation
+    Inspect {
+        /// Checkpoint to inspect
+        checkpoint: PathBuf,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Number of creatures to list in each ranking
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
+    /// Tick as fast as possible without starting the server
+    #[arg(long, required = true)]
+    headless: bool,
+
+    /// Stop after this many ticks
+    #[arg(long)]
+    ticks: Option<u64>,
+
+    /// Stop once any creature reaches this generation
+    #[arg(long)]
+    until_generation: Option<u64>,
+
+    /// Write metrics to this file, as CSV (.csv) or NDJSON (.ndjson, .jsonl)
+    #[arg(long)]
+    metrics: Option<PathBuf>,
+
+    /// Ticks between metrics samples
+    #[arg(long, default_value_t = 100)]
+    metrics_interval: u64,
+
+    /// Start from this checkpoint instead of a fresh world
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct SweepArgs {
+    /// Field to sweep, as `path=start:end:step` (end included) or
+    /// `path=a,b,c`; repeat to sweep several fields against each other
+    #[arg(long = "vary", value_name = "PATH=VALUES", value_parser = SweepAxis::parse, required = true)]
+    axes: Vec<SweepAxis>,
+
+    /// Seeds to run every combination with
+    #[arg(long, value_delimiter = ',', default_value = "1")]
+    seeds: Vec<u64>,
+
+    /// Stop each run after this many ticks
+    #[arg(long)]
+    ticks: Option<u64>,
+
+    /// Stop each run once any creature reaches this generation
+    #[arg(long)]
+    until_generation: Option<u64>,
+
+    /// Write the summary to this file, as CSV (.csv) or NDJSON (.ndjson, .jsonl)
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Runs at a time (defaults to the number of CPUs)
+    #[arg(long)]
+    jobs: Option<usize>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReplayArgs {
+    /// Journal segment to replay
+    journal: PathBuf,
+
+    /// Checkpoint the journal segment was resumed from (defaults to a fresh world from the journal's seed)
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+
+    /// First tick to report
+    #[arg(long, default_value_t = 0)]
+    from_tick: u64,
+
+    /// Last tick to report (defaults to the end of the journal)
+    #[arg(long)]
+    to_tick: Option<u64>,
+
+    /// Print every event instead of per-tick summaries
+    #[arg(long)]
+    events: bool,
 }
 
 #[tokio::main]
@@ -27,9 +170,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let args = Args::parse();
 
+    let command = match args.command {
+        Some(Command::Replay(replay_args)) => return run_replay(replay_args),
+        Some(Command::Export { checkpoint, output }) => {
+            let output = output.unwrap_or_else(|| {
+                let stem = checkpoint.file_stem().unwrap_or_default().to_string_lossy();
+                PathBuf::from(format!("{}.export.json", stem))
+            });
+            if output == checkpoint {
+                return Err("Export output would overwrite the checkpoint; pass --output".into());
+            }
+            let loaded = checkpoint::read_checkpoint(&checkpoint)?;
+            checkpoint::export_json(&loaded, &output)?;
+            log::info!("Exported {} to {}", checkpoint.display(), output.display());
+            return Ok(());
+        }
+        Some(Command::Inspect { checkpoint, json, top }) => {
+            let loaded = checkpoint::read_checkpoint(&checkpoint)?;
+            let report = InspectReport::new(&loaded, top);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("Checkpoint: {}", checkpoint.display());
+                print!("{}", report);
+            }
+            return Ok(());
+        }
+        // Run and Sweep start from the loaded config
+        command => command,
+    };
+
+    let loader = ConfigLoader {
+        path: args.config.clone(),
+        overrides: ConfigOverride::from_env().into_iter().chain(args.overrides).collect(),
+        no_checkpoint: args.no_checkpoint,
+    };
     let config = if std::path::Path::new(&args.config).exists() {
         log::info!("Loading config from: {}", args.config);
-        Config::load_from_file(&args.config)?
+        let config = Config::load_from_file(&args.config)?;
+        if args.update_config {
+            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+            let backup_path = format!("{}.backup.{}", args.config, timestamp);
+            std::fs::copy(&args.config, &backup_path)?;
+            config.save_to_file(&args.config)?;
+            log::info!("Updated {} (previous version backed up to {})", args.config, backup_path);
+        }
+        config
+    } else if args.print_config {
+        Config::default()
     } else {
         log::info!(
             "Config file not found, using defaults and saving to: {}",
@@ -39,30 +227,72 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         config.save_to_file(&args.config)?;
         config
     };
+    for item in &loader.overrides {
+        log::info!("Config override from {}", item);
+    }
+    let config = loader.apply_overrides(config)?;
+
+    if args.print_config {
+        println!("{}", serde_json::to_string_pretty(&config)?);
+    }
+
+    // Map files are read once here and reused for a fresh world below
+    let maps = match config.validate().and_then(|()| config.load_maps()) {
+        Ok(maps) => maps,
+        Err(errors) => {
+            for error in &errors.0 {
+                log::error!("Invalid config: {}", error);
+            }
+            return Err(format!(
+                "{} invalid config field(s) in {}; refusing to start",
+                errors.0.len(),
+                args.config
+            )
+            .into());
+        }
+    };
+    if args.print_config {
+        return Ok(());
+    }
+    match command {
+        Some(Command::Run(run_args)) => return run_headless(config, maps, run_args).await,
+        Some(Command::Sweep(sweep_args)) => return run_sweep(config, sweep_args).await,
+        _ => {}
+    }
 
     log::info!("Initializing simulation...");
-    let state = if !args.no_checkpoint && config.checkpoint.enabled {
-        if let Ok(Some(mut loaded_state)) = checkpoint::load_checkpoint(&config) {
-            SimulationState::apply_population_cap(
-                &mut loaded_state.creatures,
-                config.creature.max_population,
-            );
+    let state = if config.checkpoint.enabled {
+        let loaded = checkpoint::load_checkpoint(&config).map_err(|e| {
+            format!("{}. Fix or move the checkpoint, or pass --no-checkpoint to start a new world", e)
+        })?;
+        if let Some(mut loaded_state) = loaded {
+            loaded_state.prepare_resume(&config);
             log::info!("Resumed from checkpoint at tick {}", loaded_state.tick);
             loaded_state
         } else {
-            SimulationState::new(&config)
+            SimulationState::with_maps(&config, &maps)
         }
     } else {
-        SimulationState::new(&config)
+        SimulationState::with_maps(&config, &maps)
     };
 
-    let state = Arc::new(RwLock::new(state));
+    let shutdown = Shutdown::new();
+    shutdown.listen_for_signals();
+    let registry = WorldRegistry::new(config.clone(), shutdown.clone());
+    let (default_world, simulation_task) = registry.start(DEFAULT_WORLD, config.clone(), state)?;
+    if config.checkpoint.enabled {
+        registry.restore();
+    }
+
+    // The config file only configures the default world
+    tokio::spawn(watch_config_file(loader, default_world.commands.clone(), shutdown.clone()));
 
-    if !args.no_server && config.server.enabled {
-        let server_state = state.clone();
-        let server_config = config.clone();
-        tokio::spawn(async move {
-            if let Err(e) = server::run_server(server_config, server_state).await {
+    let server_task = if !args.no_server && config.server.enabled {
+        let server_registry = registry.clone();
+        let server_shutdown = shutdown.clone();
+        let task = tokio::spawn(async move {
+            let result = server::run_server(server_registry, server_shutdown).await;
+            if let Err(e) = result {
                 log::error!("Server error: {}", e);
             }
         });
@@ -71,71 +301,270 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             config.server.address,
             config.server.port
         );
+        Some(task)
+    } else {
+        None
+    };
+
+    let result = simulation_task.await?;
+
+    // Let the other worlds save their final checkpoints, and the server send
+    // close frames to its clients, before the process exits
+    shutdown.trigger(ShutdownReason::SimulationEnded);
+    if tokio::time::timeout(Duration::from_secs(30), registry.stopped()).await.is_err() {
+        log::warn!("Some worlds did not stop within 30 seconds");
+    }
+    if let Some(task) = server_task {
+        if tokio::time::timeout(Duration::from_secs(5), task).await.is_err() {
+            log::warn!("Server did not shut down within 5 seconds");
+        }
     }
 
-    run_simulation(state, config, args.no_checkpoint).await?;
+    result?;
+    log::info!("Shutdown complete");
 
     Ok(())
 }
 
-async fn run_simulation(
-    state: Arc<RwLock<SimulationState>>,
-    config: Config,
-    no_checkpoint: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let tick_duration = Duration::from_millis(1000 / config.simulation.ticks_per_second);
-    let mut tick_interval = interval(tick_duration);
+/// Run an unpaced batch experiment and save a final checkpoint
+async fn run_headless(config: Config, maps: WorldMaps, args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = match &args.checkpoint {
+        Some(path) => {
+            let mut state = checkpoint::load_checkpoint_from(path)?;
+            state.prepare_resume(&config);
+            log::info!("Starting from {} at tick {}", path.display(), state.tick);
+            state
+        }
+        None => SimulationState::with_maps(&config, &maps),
+    };
 
-    let checkpoint_interval = if !no_checkpoint && config.checkpoint.enabled {
-        Some(Duration::from_secs(config.checkpoint.interval_seconds))
-    } else {
-        None
+    let mut metrics = match &args.metrics {
+        Some(path) => {
+            let format = MetricsFormat::from_path(path)
+                .ok_or_else(|| format!("Can't tell the metrics format of {}; use .csv or .ndjson", path.display()))?;
+            Some(MetricsWriter::create(path, format)?)
+        }
+        None => None,
+    };
+
+    let options = BatchOptions {
+        max_ticks: args.ticks,
+        target_generation: args.until_generation,
+        metrics_interval: args.metrics_interval,
+    };
+    if options.max_ticks.is_none() && options.target_generation.is_none() {
+        log::warn!("No --ticks or --until-generation given; running until extinction or Ctrl+C");
+    }
+
+    let shutdown = Shutdown::new();
+    shutdown.listen_for_signals();
+
+    log::info!("Running headless from tick {}", state.tick);
+    let (state, config, summary) = tokio::task::spawn_blocking(move || {
+        let summary = run_batch(&mut state, &config, &options, metrics.as_mut(), || shutdown.is_triggered())
+            .map_err(|e| e.to_string());
+        (state, config, summary)
+    })
+    .await?;
+    let summary = summary?;
+
+    let m = &summary.final_metrics;
+    log::info!(
+        "Stopped at tick {} ({}) after {} ticks in {:.1}s ({:.0} ticks/s) | Population: {} | Max Gen: {}",
+        m.tick,
+        summary.reason,
+        summary.ticks_run,
+        summary.elapsed_seconds,
+        summary.ticks_run as f64 / summary.elapsed_seconds.max(f64::EPSILON),
+        m.population,
+        m.max_generation
+    );
+    if let Some(path) = &args.metrics {
+        log::info!("Metrics written to {}", path.display());
+    }
+
+    if config.checkpoint.enabled {
+        let path = checkpoint::save_checkpoint(&state, &config)?;
+        log::info!("Final checkpoint saved: {}", path);
+    }
+
+    Ok(())
+}
+
+/// Run a parameter sweep on a rayon pool and print a summary table
+async fn run_sweep(config: Config, args: SweepArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.ticks.is_none() && args.until_generation.is_none() {
+        return Err("Pass --ticks or --until-generation so every sweep run ends".into());
+    }
+    let format = match &args.output {
+        Some(path) => Some(
+            MetricsFormat::from_path(path)
+                .ok_or_else(|| format!("Can't tell the summary format of {}; use .csv or .ndjson", path.display()))?,
+        ),
+        None => None,
     };
 
-    let mut last_checkpoint = Instant::now();
-    let mut last_log = Instant::now();
-    let log_interval = Duration::from_secs(10);
+    let runs = sweep::plan(&config, &args.axes, &args.seeds)?;
+    let options = BatchOptions {
+        max_ticks: args.ticks,
+        target_generation: args.until_generation,
+        metrics_interval: 1,
+    };
+    let mut pool = rayon::ThreadPoolBuilder::new();
+    if let Some(jobs) = args.jobs {
+        pool = pool.num_threads(jobs);
+    }
+    let pool = pool.build()?;
+
+    let shutdown = Shutdown::new();
+    shutdown.listen_for_signals();
+
+    let total = runs.len();
+    log::info!("Sweeping {} runs on {} threads", total, pool.current_num_threads());
+    let finished = AtomicUsize::new(0);
+    let summary = tokio::task::spawn_blocking(move || {
+        pool.install(|| {
+            sweep::run_sweep(
+                runs,
+                &options,
+                || shutdown.is_triggered(),
+                |result| {
+                    let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                    log::info!(
+                        "Run {} finished ({}/{}): {} at tick {}, population {}",
+                        result.run,
+                        done,
+                        total,
+                        result.reason,
+                        result.ticks,
+                        result.final_population
+                    );
+                },
+            )
+        })
+    })
+    .await??;
+
+    print!("{}", summary);
+    if let (Some(path), Some(format)) = (&args.output, format) {
+        summary.write(path, format)?;
+        log::info!("Summary written to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Where the config is read from, and the environment variables and flags
+/// that override it. Reloads go through the same overrides so they don't
+/// show up as changes.
+#[derive(Debug, Clone)]
+struct ConfigLoader {
+    path: String,
+    /// EVO_* environment variables followed by `--set` flags
+    overrides: Vec<ConfigOverride>,
+    no_checkpoint: bool,
+}
+
+impl ConfigLoader {
+    fn apply_overrides(&self, config: Config) -> Result<Config, Box<dyn std::error::Error>> {
+        let mut config = config.with_overrides(&self.overrides)?;
+
+        if self.no_checkpoint {
+            config.checkpoint.enabled = false;
+            config.checkpoint.journal_enabled = false;
+        }
+        Ok(config)
+    }
+
+    fn load(&self) -> Result<Config, Box<dyn std::error::Error>> {
+        self.apply_overrides(Config::load_from_file(&self.path)?)
+    }
+}
+
+/// Reload the config file whenever it is modified and hand it to the tick
+/// loop, which applies or rejects it as a whole
+async fn watch_config_file(loader: ConfigLoader, commands: CommandSender, shutdown: Shutdown) {
+    let modified = |path: &str| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last_modified = modified(&loader.path);
+    let mut poll = interval(CONFIG_POLL_INTERVAL);
 
     loop {
-        tick_interval.tick().await;
-
-        {
-            let mut sim_state = state.write().await;
-            sim_state.tick(&config);
-
-            if last_log.elapsed() >= log_interval {
-                let metrics = sim_state.metrics();
-                log::info!(
-                    "Tick: {} | Population: {} | Avg Energy: {:.2} | Max Gen: {} | Food: {}",
-                    metrics.tick,
-                    metrics.population,
-                    metrics.avg_energy,
-                    metrics.max_generation,
-                    metrics.total_food
-                );
-                last_log = Instant::now();
-
-                if metrics.population == 0 {
-                    log::warn!("All creatures have died! Simulation ended.");
-                    break;
-                }
+        tokio::select! {
+            _ = poll.tick() => {}
+            _ = shutdown.wait() => break,
+        }
+
+        let current = modified(&loader.path);
+        if current == last_modified {
+            continue;
+        }
+        last_modified = current;
+
+        let config = match loader.load() {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!("Not reloading {}: {}", loader.path, e);
+                continue;
             }
+        };
+
+        let (done, applied) = oneshot::channel();
+        let command = SimulationCommand::UpdateConfig {
+            config: Box::new(config),
+            source: loader.path.clone(),
+            done,
+        };
+        if commands.send(command).is_err() {
+            break;
+        }
+        match applied.await {
+            Ok(Ok(update)) if update.changes.is_empty() => {
+                log::info!("{} was modified but no settings changed", loader.path);
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => log::error!("Rejected config change from {}: {}", loader.path, e),
+            Err(_) => break,
         }
+    }
+}
+
+fn run_replay(args: ReplayArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = Replay::open(&args.journal, args.checkpoint.as_deref())?;
+    log::info!(
+        "Replaying {} from tick {}",
+        args.journal.display(),
+        replay.state().tick
+    );
+
+    if !replay.seek(args.from_tick)? {
+        log::warn!("Journal ends before tick {}", args.from_tick);
+        return Ok(());
+    }
 
-        if let Some(checkpoint_dur) = checkpoint_interval {
-            if last_checkpoint.elapsed() >= checkpoint_dur {
-                let sim_state = state.read().await;
-                match checkpoint::save_checkpoint(&sim_state, &config) {
-                    Ok(path) => {
-                        log::info!("Checkpoint saved: {}", path);
-                    }
-                    Err(e) => {
-                        log::error!("Failed to save checkpoint: {}", e);
-                    }
-                }
-                last_checkpoint = Instant::now();
+    while args.to_tick.is_none_or(|to_tick| replay.state().tick <= to_tick) {
+        let Some(step) = replay.step()? else {
+            break;
+        };
+
+        if args.events {
+            for event in &step.events {
+                println!("{} {:?}", step.tick, event);
             }
+            continue;
         }
+
+        let count = |predicate: fn(&SimEvent) -> bool| step.events.iter().filter(|e| predicate(e)).count();
+        println!(
+            "Tick: {} | Population: {} | Births: {} | Deaths: {} | Moves: {} | Attacks: {} | Shares: {} | Food spawns: {}",
+            step.tick,
+            replay.state().creatures.len(),
+            count(|e| matches!(e, SimEvent::Birth { .. } | SimEvent::Resurrection { .. })),
+            count(|e| matches!(e, SimEvent::Death { .. })),
+            count(|e| matches!(e, SimEvent::Move { .. })),
+            count(|e| matches!(e, SimEvent::Attack { .. })),
+            count(|e| matches!(e, SimEvent::Share { .. })),
+            count(|e| matches!(e, SimEvent::FoodSpawn { .. })),
+        );
     }
 
     Ok(())
@@ -0,0 +1,259 @@
+use super::{internal_error, ApiError, AppState};
+use crate::checkpoint::{self, format::CheckpointSummary, storage};
+use crate::config::CheckpointFormat;
+use crate::simulation::control::SimulationCommand;
+use crate::simulation::registry::WorldHandle;
+use axum::{
+    extract::{Path as UrlPath, State as AxumState},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::sync::oneshot;
+
+#[derive(Debug, Serialize)]
+pub struct CheckpointInfo {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+    pub format: CheckpointFormat,
+    #[serde(flatten)]
+    pub summary: Option<CheckpointSummary>,
+    /// Why the summary could not be read, e.g. a corrupt file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Path parameters of a checkpoint route, which may also name the world
+#[derive(Debug, Deserialize)]
+pub struct CheckpointName {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoadResult {
+    pub name: String,
+    pub tick: u64,
+}
+
+/// Resolve a checkpoint by file name. Only files listed in the checkpoint
+/// directory are accepted, so names can't reach outside it.
+fn find_checkpoint(directory: &str, name: &str) -> Result<PathBuf, ApiError> {
+    storage::list_checkpoints(directory)
+        .into_iter()
+        .map(|c| c.path)
+        .find(|path| path.file_name().and_then(|n| n.to_str()) == Some(name))
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No checkpoint named {}", name)))
+}
+
+/// GET /api/checkpoints: newest first, with tick, population and max generation
+pub async fn list_handler(
+    AxumState(state): AxumState<AppState>,
+    world: WorldHandle,
+) -> Result<Json<Vec<CheckpointInfo>>, ApiError> {
+    let directory = world.config.get().checkpoint.directory.clone();
+    let cache = state.checkpoint_summaries.clone();
+
+    let checkpoints = tokio::task::spawn_blocking(move || {
+        let entries = storage::list_checkpoints(&directory);
+        let mut cache = cache.lock().unwrap();
+        // Checkpoint files are never rewritten in place, so a cached summary
+        // stays valid for as long as its file exists
+        cache.retain(|path, _| entries.iter().any(|c| &c.path == path));
+
+        entries
+            .into_iter()
+            .map(|entry| {
+                let summary = match cache.get(&entry.path) {
+                    Some(summary) => Ok(*summary),
+                    None => checkpoint::read_summary(&entry.path).map_err(|e| e.to_string()),
+                };
+                if let Ok(summary) = summary {
+                    cache.insert(entry.path.clone(), summary);
+                }
+
+                CheckpointInfo {
+                    name: entry.path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                    created_at: entry.timestamp,
+                    size_bytes: std::fs::metadata(&entry.path).map(|m| m.len()).unwrap_or(0),
+                    format: entry
+                        .path
+                        .extension()
+                        .and_then(|s| s.to_str())
+                        .and_then(CheckpointFormat::from_extension)
+                        .unwrap_or_default(),
+                    summary: summary.as_ref().ok().copied(),
+                    error: summary.err(),
+                }
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(checkpoints))
+}
+
+/// POST /api/checkpoints: save the current state right away
+pub async fn save_handler(
+    AxumState(state): AxumState<AppState>,
+    world: WorldHandle,
+) -> Result<impl IntoResponse, ApiError> {
+    let config = world.config.get();
+    if !config.checkpoint.enabled {
+        return Err((StatusCode::CONFLICT, "Checkpointing is disabled".to_string()));
+    }
+
+    // Save from a snapshot so the tick loop isn't held up while encoding
+    let snapshot = world.snapshot().await;
+    let format = config.checkpoint.format;
+    let (path, summary) = tokio::task::spawn_blocking(move || {
+        checkpoint::save_checkpoint(&snapshot, &config)
+            .map(|path| (PathBuf::from(path), CheckpointSummary::of(&snapshot)))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(internal_error)?
+    .map_err(internal_error)?;
+
+    log::info!("Checkpoint saved via API: {}", path.display());
+    state.checkpoint_summaries.lock().unwrap().insert(path.clone(), summary);
+
+    let info = CheckpointInfo {
+        name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        created_at: Utc::now(),
+        size_bytes: std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        format,
+        summary: Some(summary),
+        error: None,
+    };
+    Ok((StatusCode::CREATED, Json(info)))
+}
+
+/// GET /api/checkpoints/{name}: download the checkpoint file
+pub async fn download_handler(
+    world: WorldHandle,
+    UrlPath(CheckpointName { name }): UrlPath<CheckpointName>,
+) -> Result<Response, ApiError> {
+    let path = find_checkpoint(&world.config.get().checkpoint.directory, &name)?;
+    let bytes = tokio::fs::read(&path).await.map_err(internal_error)?;
+
+    let content_type = match path.extension().and_then(|s| s.to_str()) {
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    };
+    let headers = [
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", name)),
+    ];
+    Ok((headers, bytes).into_response())
+}
+
+/// POST /api/checkpoints/{name}/load: replace the running simulation
+pub async fn load_handler(
+    world: WorldHandle,
+    UrlPath(CheckpointName { name }): UrlPath<CheckpointName>,
+) -> Result<Json<LoadResult>, ApiError> {
+    let path = find_checkpoint(&world.config.get().checkpoint.directory, &name)?;
+
+    let load_path = path.clone();
+    let loaded = tokio::task::spawn_blocking(move || checkpoint::load_checkpoint_from(&load_path).map_err(|e| e.to_string()))
+        .await
+        .map_err(internal_error)?
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, format!("Failed to load {}: {}", name, e)))?;
+
+    let (done, resumed) = oneshot::channel();
+    world
+        .commands
+        .send(SimulationCommand::Load {
+            state: Box::new(loaded),
+            source: path,
+            done,
+        })
+        .map_err(|_| internal_error("Simulation is not running"))?;
+    let tick = resumed.await.map_err(|_| internal_error("Simulation stopped before loading"))?;
+
+    Ok(Json(LoadResult { name, tick }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::TestServer;
+    use axum::http::{Method, StatusCode};
+
+    #[tokio::test]
+    async fn test_save_list_and_load() {
+        let server = TestServer::start("checkpoints", |_| {});
+        let (status, _) = server.send(Method::POST, "/api/simulation/pause", None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (tick, population, max_generation) = {
+            let state = server.world.state.read().await;
+            (state.tick, state.creatures.len(), state.max_generation())
+        };
+
+        let (status, saved) = server.send(Method::POST, "/api/checkpoints", None).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let name = saved["name"].as_str().unwrap().to_string();
+
+        let (status, list) = server.send(Method::GET, "/api/checkpoints", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let list = list.as_array().unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0]["name"], name.as_str());
+        assert_eq!(list[0]["tick"], tick);
+        assert_eq!(list[0]["population"], population);
+        assert_eq!(list[0]["max_generation"], max_generation);
+
+        // Move the running state away from the checkpoint, then load it back
+        {
+            let mut state = server.world.state.write().await;
+            state.tick += 100;
+            state.creatures.retain(|&id, _| id % 2 == 0);
+        }
+        let (status, loaded) = server
+            .send(Method::POST, &format!("/api/checkpoints/{}/load", name), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(loaded["tick"], tick);
+
+        let state = server.world.state.read().await;
+        assert_eq!(state.tick, tick);
+        assert_eq!(state.creatures.len(), population);
+        drop(state);
+
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_unknown_names_are_not_found() {
+        let server = TestServer::start("checkpoint_names", |_| {});
+        let (status, _) = server.send(Method::POST, "/api/checkpoints", None).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        for name in ["missing.bin", "..%2Fevo_outside.bin", "%2Fetc%2Fpasswd", ".."] {
+            let (status, _) = server.send(Method::GET, &format!("/api/checkpoints/{}", name), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "download {}", name);
+            let (status, _) = server
+                .send(Method::POST, &format!("/api/checkpoints/{}/load", name), None)
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "load {}", name);
+        }
+
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_save_when_disabled_conflicts() {
+        let server = TestServer::start("checkpoints_disabled", |config| config.checkpoint.enabled = false);
+
+        let (status, _) = server.send(Method::POST, "/api/checkpoints", None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(!server.directory.exists());
+
+        server.stop().await;
+    }
+}
@@ -0,0 +1,43 @@
+use super::{internal_error, ApiError};
+use crate::config::reload::ConfigUpdate;
+use crate::config::Config;
+use crate::simulation::control::SimulationCommand;
+use crate::simulation::registry::WorldHandle;
+use axum::{http::StatusCode, Json};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+/// GET /api/config: the config the simulation is running with
+pub async fn get_handler(world: WorldHandle) -> Json<Config> {
+    Json((*world.config.get()).clone())
+}
+
+/// PATCH /api/config: merge a partial config into the running one. The
+/// change is applied at the next tick boundary, or rejected as a whole.
+pub async fn patch_handler(
+    world: WorldHandle,
+    Json(patch): Json<Value>,
+) -> Result<Json<ConfigUpdate>, ApiError> {
+    let config = world
+        .config
+        .get()
+        .patched(&patch)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    let (done, applied) = oneshot::channel();
+    world
+        .commands
+        .send(SimulationCommand::UpdateConfig {
+            config: Box::new(config),
+            source: "API".to_string(),
+            done,
+        })
+        .map_err(|_| internal_error("Simulation is not running"))?;
+
+    let update = applied
+        .await
+        .map_err(|_| internal_error("Simulation stopped before applying the change"))?
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    Ok(Json(update))
+}
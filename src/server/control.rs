@@ -0,0 +1,99 @@
+use super::{internal_error, ApiError};
+use crate::simulation::control::{RunControl, RunState, SimulationCommand};
+use crate::simulation::registry::WorldHandle;
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use tokio::sync::oneshot;
+
+/// A change to the run state, from a REST or WebSocket client
+#[derive(Debug, Clone, Copy)]
+pub enum RunRequest {
+    Control(RunControl),
+    SetSpeed(u64),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StepQuery {
+    ticks: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpeedRequest {
+    ticks_per_second: u64,
+}
+
+/// Send `request` to the tick loop and wait for the resulting run state
+pub async fn request(world: &WorldHandle, request: RunRequest) -> Result<RunState, ApiError> {
+    match request {
+        RunRequest::Control(control) => {
+            let (done, applied) = oneshot::channel();
+            world
+                .commands
+                .send(SimulationCommand::Control { control, done })
+                .map_err(|_| internal_error("Simulation is not running"))?;
+            applied
+                .await
+                .map_err(|_| internal_error("Simulation stopped before applying the change"))
+        }
+        // Speed is a config field, so it goes through the same validation
+        // and change log as any other live config change
+        RunRequest::SetSpeed(ticks_per_second) => {
+            let mut config = (*world.config.get()).clone();
+            config.simulation.ticks_per_second = ticks_per_second;
+
+            let (done, applied) = oneshot::channel();
+            world
+                .commands
+                .send(SimulationCommand::UpdateConfig {
+                    config: Box::new(config),
+                    source: "speed control".to_string(),
+                    done,
+                })
+                .map_err(|_| internal_error("Simulation is not running"))?;
+            applied
+                .await
+                .map_err(|_| internal_error("Simulation stopped before applying the change"))?
+                .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+            Ok(*world.run_state.borrow())
+        }
+    }
+}
+
+/// GET /api/simulation: whether the simulation is paused, and its speed
+pub async fn get_handler(world: WorldHandle) -> Json<RunState> {
+    Json(*world.run_state.borrow())
+}
+
+/// POST /api/simulation/pause
+pub async fn pause_handler(world: WorldHandle) -> Result<Json<RunState>, ApiError> {
+    request(&world, RunRequest::Control(RunControl::Pause)).await.map(Json)
+}
+
+/// POST /api/simulation/resume
+pub async fn resume_handler(world: WorldHandle) -> Result<Json<RunState>, ApiError> {
+    request(&world, RunRequest::Control(RunControl::Resume)).await.map(Json)
+}
+
+/// POST /api/simulation/step?ticks=N: run N ticks (default 1), then stay paused
+pub async fn step_handler(
+    world: WorldHandle,
+    Query(query): Query<StepQuery>,
+) -> Result<Json<RunState>, ApiError> {
+    let control = RunControl::Step {
+        ticks: query.ticks.unwrap_or(1),
+    };
+    request(&world, RunRequest::Control(control)).await.map(Json)
+}
+
+/// POST /api/simulation/speed with `{"ticks_per_second": N}`
+pub async fn speed_handler(
+    world: WorldHandle,
+    Json(speed): Json<SpeedRequest>,
+) -> Result<Json<RunState>, ApiError> {
+    request(&world, RunRequest::SetSpeed(speed.ticks_per_second)).await.map(Json)
+}
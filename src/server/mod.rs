@@ -1,92 +1,174 @@
+mod checkpoints;
+mod config;
+mod control;
 pub mod protocol;
-pub mod state_stream;
+mod worlds;
 
+use crate::checkpoint::format::CheckpointSummary;
 use crate::config::Config;
-use crate::simulation::SimulationState;
+use crate::shutdown::{Shutdown, ShutdownReason};
+use crate::simulation::control::RunControl;
+use crate::simulation::registry::{WorldHandle, WorldRegistry};
 use axum::{
-    extract::{ws::WebSocket, State as AxumState, WebSocketUpgrade},
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket},
+        State as AxumState, WebSocketUpgrade,
+    },
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
     Router,
 };
 use futures_util::{SinkExt, StreamExt};
+use control::RunRequest;
 use protocol::{ClientMessage, ServerMessage};
-use state_stream::StateStream;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use tokio::time::{interval, Duration};
 use tower_http::services::ServeDir;
 
+type ApiError = (StatusCode, String);
+
+fn internal_error(e: impl std::fmt::Display) -> ApiError {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
 #[derive(Clone)]
 struct AppState {
-    stream: StateStream,
-    config: Config,
+    registry: WorldRegistry,
+    shutdown: Shutdown,
+    /// Summaries of checkpoint files already read, keyed by path
+    checkpoint_summaries: Arc<Mutex<HashMap<PathBuf, CheckpointSummary>>>,
 }
 
-pub async fn run_server(
-    config: Config,
-    state: Arc<RwLock<SimulationState>>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let addr = format!("{}:{}", config.server.address, config.server.port);
-    let stream = StateStream::new(state.clone());
+/// Serve HTTP and WebSocket clients until `shutdown` is triggered, then close
+/// every WebSocket connection with a close frame.
+pub async fn run_server(registry: WorldRegistry, shutdown: Shutdown) -> Result<(), Box<dyn std::error::Error>> {
+    let server_config = &registry.base_config().server;
+    let addr = format!("{}:{}", server_config.address, server_config.port);
 
     let app_state = AppState {
-        stream,
-        config: config.clone(),
+        registry,
+        shutdown: shutdown.clone(),
+        checkpoint_summaries: Default::default(),
     };
 
-    // Build the router
-    let app = Router::new()
-        // WebSocket endpoint
-        .route("/ws", get(websocket_handler))
-        // Restart endpoint
-        .route("/api/restart", post(restart_handler))
-        // Serve static files from the "static" directory
-        .nest_service("/", ServeDir::new("static"))
-        .with_state(app_state);
+    let app = router(app_state);
 
     log::info!("HTTP server with WebSocket listening on: {}", addr);
     log::info!("Static files served from: ./static/");
     log::info!("WebSocket endpoint: ws://{}/ws", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            shutdown.wait().await;
+        })
+        .await?;
 
     Ok(())
 }
 
-async fn websocket_handler(
-    ws: WebSocketUpgrade,
-    AxumState(state): AxumState<AppState>,
-) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_websocket(socket, state))
+/// Every HTTP and WebSocket route, bound to `app_state`
+fn router(app_state: AppState) -> Router {
+    // Routes for a single world, served under /api for the default world and
+    // under /api/worlds/{world_id} for any world
+    let world_routes = Router::new()
+        // Run control
+        .route("/simulation", get(control::get_handler))
+        .route("/simulation/pause", post(control::pause_handler))
+        .route("/simulation/resume", post(control::resume_handler))
+        .route("/simulation/step", post(control::step_handler))
+        .route("/simulation/speed", post(control::speed_handler))
+        // Live config changes
+        .route("/config", get(config::get_handler).patch(config::patch_handler))
+        // Checkpoint management
+        .route(
+            "/checkpoints",
+            get(checkpoints::list_handler).post(checkpoints::save_handler),
+        )
+        .route("/checkpoints/:name", get(checkpoints::download_handler))
+        .route("/checkpoints/:name/load", post(checkpoints::load_handler));
+
+    // Build the router
+    Router::new()
+        // WebSocket endpoints
+        .route("/ws", get(websocket_handler))
+        .route("/ws/:world_id", get(websocket_handler))
+        // Restart endpoint
+        .route("/api/restart", post(restart_handler))
+        // World management
+        .route("/api/worlds", get(worlds::list_handler).post(worlds::create_handler))
+        .route(
+            "/api/worlds/:world_id",
+            get(worlds::get_handler).delete(worlds::delete_handler),
+        )
+        .route("/api/worlds/:world_id/clone", post(worlds::clone_handler))
+        .nest("/api", world_routes.clone())
+        .nest("/api/worlds/:world_id", world_routes)
+        // Serve static files from the "static" directory
+        .nest_service("/", ServeDir::new("static"))
+        .with_state(app_state)
+}
+
+async fn websocket_handler(ws: WebSocketUpgrade, world: WorldHandle) -> impl IntoResponse {
+    log::info!("Client connected to world {}", world.id);
+    ws.on_upgrade(|socket| handle_websocket(socket, world))
 }
 
-async fn restart_handler() -> impl IntoResponse {
-    log::warn!("Restart requested via API - shutting down process");
+async fn restart_handler(AxumState(state): AxumState<AppState>) -> impl IntoResponse {
+    log::warn!("Restart requested via API - saving a checkpoint and shutting down");
 
-    // Spawn a task to exit the process after a short delay
-    // This allows the HTTP response to be sent before the process exits
-    tokio::spawn(async {
-        tokio::time::sleep(Duration::from_millis(100)).await;
-        std::process::exit(0);
-    });
+    // The process exits once the tick loop has stopped and the final
+    // checkpoint is written; a supervisor is expected to start it again
+    state.shutdown.trigger(ShutdownReason::Restart);
 
     (StatusCode::OK, "Server restart initiated")
 }
 
-async fn handle_websocket(socket: WebSocket, app_state: AppState) {
+async fn handle_websocket(socket: WebSocket, world: WorldHandle) {
     let (mut sender, mut receiver) = socket.split();
     let mut update_interval = interval(Duration::from_millis(
-        1000 / app_state.config.server.update_rate_hz,
+        1000 / world.config.get().server.update_rate_hz,
     ));
     let mut subscribed_creature_id: Option<u64> = None;
 
+    let mut run_state = world.run_state.clone();
+    let initial = ServerMessage::RunState(*run_state.borrow_and_update());
+    if let Ok(json) = serde_json::to_string(&initial) {
+        let _ = sender.send(Message::Text(json)).await;
+    }
+
     loop {
         tokio::select! {
+            reason = world.shutdown.wait() => {
+                let reason = match reason {
+                    ShutdownReason::Restart => "Server restarting",
+                    ShutdownReason::Signal | ShutdownReason::SimulationEnded => "Server shutting down",
+                    ShutdownReason::Deleted => "World deleted",
+                };
+                let close = Message::Close(Some(CloseFrame {
+                    code: close_code::AWAY,
+                    reason: reason.into(),
+                }));
+                let _ = sender.send(close).await;
+                let _ = sender.close().await;
+                break;
+            }
+
+            Ok(()) = run_state.changed() => {
+                let message = ServerMessage::RunState(*run_state.borrow_and_update());
+                if let Ok(json) = serde_json::to_string(&message) {
+                    if sender.send(Message::Text(json)).await.is_err() {
+                        log::info!("Client disconnected");
+                        break;
+                    }
+                }
+            }
+
             _ = update_interval.tick() => {
-                let state = app_state.stream.get_state().await;
+                let state = world.snapshot().await;
                 let metrics = state.metrics();
                 let creatures = state.creatures_vec();
 
@@ -101,7 +183,7 @@ async fn handle_websocket(socket: WebSocket, app_state: AppState) {
 
                 // Send creature updates if subscribed
                 if let Some(creature_id) = subscribed_creature_id {
-                    if let Some(details) = get_creature_details(&state, creature_id, &app_state.config) {
+                    if let Some(details) = get_creature_details(&state, creature_id, &world.config.get()) {
                         let update_msg = ServerMessage::CreatureUpdate { details };
                         if let Ok(json) = serde_json::to_string(&update_msg) {
                             let _ = sender.send(axum::extract::ws::Message::Text(json)).await;
@@ -116,7 +198,7 @@ async fn handle_websocket(socket: WebSocket, app_state: AppState) {
                         if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
                             match client_msg {
                                 ClientMessage::GetState => {
-                                    let state = app_state.stream.get_state().await;
+                                    let state = world.snapshot().await;
                                     let metrics = state.metrics();
                                     let creatures = state.creatures_vec();
                                     let message = ServerMessage::full_state(metrics, &state.world, creatures);
@@ -129,8 +211,8 @@ async fn handle_websocket(socket: WebSocket, app_state: AppState) {
                                     log::warn!("GetRegion not yet implemented");
                                 }
                                 ClientMessage::GetCreatureDetails { creature_id } => {
-                                    let state = app_state.stream.get_state().await;
-                                    if let Some(details) = get_creature_details(&state, creature_id, &app_state.config) {
+                                    let state = world.snapshot().await;
+                                    if let Some(details) = get_creature_details(&state, creature_id, &world.config.get()) {
                                         let message = ServerMessage::CreatureDetails(details);
                                         if let Ok(json) = serde_json::to_string(&message) {
                                             let _ = sender.send(axum::extract::ws::Message::Text(json)).await;
@@ -140,6 +222,18 @@ async fn handle_websocket(socket: WebSocket, app_state: AppState) {
                                 ClientMessage::SubscribeCreature { creature_id } => {
                                     subscribed_creature_id = creature_id;
                                 }
+                                // Everything else changes the run state. Every
+                                // client, this one included, hears about the new
+                                // state through the run state broadcast.
+                                other => {
+                                    let Some(request) = run_request(&other) else { continue };
+                                    if let Err((_, message)) = control::request(&world, request).await {
+                                        let error = ServerMessage::Error { message };
+                                        if let Ok(json) = serde_json::to_string(&error) {
+                                            let _ = sender.send(Message::Text(json)).await;
+                                        }
+                                    }
+                                }
                             }
                         }
                     }
@@ -160,6 +254,22 @@ async fn handle_websocket(socket: WebSocket, app_state: AppState) {
     log::info!("WebSocket connection closed");
 }
 
+/// The run state change a client message asks for, if any
+fn run_request(message: &ClientMessage) -> Option<RunRequest> {
+    match *message {
+        ClientMessage::Pause => Some(RunRequest::Control(RunControl::Pause)),
+        ClientMessage::Resume => Some(RunRequest::Control(RunControl::Resume)),
+        ClientMessage::Step { ticks } => Some(RunRequest::Control(RunControl::Step {
+            ticks: ticks.unwrap_or(1),
+        })),
+        ClientMessage::SetSpeed { ticks_per_second } => Some(RunRequest::SetSpeed(ticks_per_second)),
+        ClientMessage::GetState
+        | ClientMessage::GetRegion { .. }
+        | ClientMessage::GetCreatureDetails { .. }
+        | ClientMessage::SubscribeCreature { .. } => None,
+    }
+}
+
 fn get_creature_details(
     state: &crate::simulation::SimulationState,
     creature_id: u64,
@@ -185,3 +295,85 @@ fn get_creature_details(
         network_probabilities,
     })
 }
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::shutdown::ShutdownReason;
+    use crate::simulation::registry::DEFAULT_WORLD;
+    use crate::simulation::SimulationState;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Method, Request};
+    use serde_json::Value;
+    use tokio::task::JoinHandle;
+    use tower::Service;
+
+    /// A server around a small default world, with its own checkpoint
+    /// directory
+    pub struct TestServer {
+        pub app: Router,
+        pub world: WorldHandle,
+        pub directory: PathBuf,
+        shutdown: Shutdown,
+        task: JoinHandle<Result<(), String>>,
+    }
+
+    impl TestServer {
+        pub fn start(name: &str, configure: impl FnOnce(&mut Config)) -> Self {
+            let directory = std::env::temp_dir().join(format!("evo_server_{}_{}", name, std::process::id()));
+            let mut config = Config::default();
+            config.world.width = 30;
+            config.world.height = 30;
+            config.creature.initial_population = 20;
+            config.checkpoint.directory = directory.to_string_lossy().to_string();
+            configure(&mut config);
+
+            let shutdown = Shutdown::new();
+            let registry = WorldRegistry::new(config.clone(), shutdown.clone());
+            let state = SimulationState::new(&config);
+            let (world, task) = registry.start(DEFAULT_WORLD, config, state).unwrap();
+            let app = router(AppState {
+                registry,
+                shutdown: shutdown.clone(),
+                checkpoint_summaries: Default::default(),
+            });
+
+            Self {
+                app,
+                world,
+                directory,
+                shutdown,
+                task,
+            }
+        }
+
+        /// Send a request with an optional JSON body and return the status
+        /// and the body, parsed as JSON if it is any
+        pub async fn send(&self, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+            let request = Request::builder().method(method).uri(uri);
+            let request = match body {
+                Some(body) => request
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string())),
+                None => request.body(Body::empty()),
+            }
+            .unwrap();
+
+            // A router is always ready, so it can be called without polling first
+            let response = self.app.clone().call(request).await.unwrap();
+            let status = response.status();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body = serde_json::from_slice(&bytes)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).to_string()));
+            (status, body)
+        }
+
+        /// Stop every world without a final checkpoint and remove the
+        /// checkpoint directory
+        pub async fn stop(self) {
+            self.shutdown.trigger(ShutdownReason::Deleted);
+            let _ = self.task.await;
+            let _ = std::fs::remove_dir_all(&self.directory);
+        }
+    }
+}
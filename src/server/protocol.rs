@@ -1,4 +1,5 @@
 use crate::creature::Creature;
+use crate::simulation::control::RunState;
 use crate::stats::SimulationMetrics;
 use crate::world::World;
 use serde::{Deserialize, Serialize};
@@ -25,6 +26,10 @@ pub enum ServerMessage {
         metrics: SimulationMetrics,
         world_width: usize,
         world_height: usize,
+        /// Terrain code of each cell, row by row (0 plain, 1 wall, 2 water,
+        /// 3 rough); omitted when every cell is plain
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        terrain: Vec<u8>,
         creatures: Vec<CreatureSnapshot>,
         food: Vec<FoodSnapshot>,
     },
@@ -34,6 +39,13 @@ pub enum ServerMessage {
     CreatureUpdate {
         details: CreatureDetails,
     },
+    /// Sent on connect and whenever the simulation is paused, resumed,
+    /// stepped or changes speed
+    #[serde(rename = "run_state")]
+    RunState(RunState),
+    /// A client request that could not be carried out
+    #[serde(rename = "error")]
+    Error { message: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -85,6 +97,15 @@ pub enum ClientMessage {
     GetCreatureDetails { creature_id: u64 },
     #[serde(rename = "subscribe_creature")]
     SubscribeCreature { creature_id: Option<u64> },
+    #[serde(rename = "pause")]
+    Pause,
+    #[serde(rename = "resume")]
+    Resume,
+    /// Run `ticks` ticks (1 if omitted), then stay paused
+    #[serde(rename = "step")]
+    Step { ticks: Option<u64> },
+    #[serde(rename = "set_speed")]
+    SetSpeed { ticks_per_second: u64 },
 }
 
 impl ServerMessage {
@@ -145,6 +166,7 @@ impl ServerMessage {
             metrics,
             world_width: world.width(),
             world_height: world.height(),
+            terrain: world.terrain_layer().iter().map(|&t| u8::from(t)).collect(),
             creatures: snapshots,
             food,
         }
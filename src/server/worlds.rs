@@ -0,0 +1,191 @@
+use super::{ApiError, AppState};
+use crate::simulation::control::RunState;
+use crate::simulation::registry::{WorldError, WorldHandle, DEFAULT_WORLD};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path as UrlPath, State as AxumState},
+    http::{request::Parts, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize)]
+pub struct WorldInfo {
+    pub id: String,
+    /// False once every creature has died
+    pub running: bool,
+    #[serde(flatten)]
+    pub run_state: RunState,
+    pub tick: u64,
+    pub population: usize,
+    pub max_generation: u64,
+    pub checkpoint_directory: String,
+}
+
+impl WorldInfo {
+    async fn of(world: &WorldHandle) -> Self {
+        let state = world.state.read().await;
+        Self {
+            id: world.id.clone(),
+            running: world.is_running(),
+            run_state: *world.run_state.borrow(),
+            tick: state.tick,
+            population: state.creatures.len(),
+            max_generation: state.max_generation(),
+            checkpoint_directory: world.config.get().checkpoint.directory.clone(),
+        }
+    }
+}
+
+/// Body of a create or clone request. Both fields are optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct WorldRequest {
+    id: Option<String>,
+    /// Partial config merged into the server's config, or the source
+    /// world's when cloning
+    config: Option<Value>,
+}
+
+fn world_error(e: WorldError) -> ApiError {
+    let status = match e {
+        WorldError::NotFound(_) => StatusCode::NOT_FOUND,
+        WorldError::AlreadyExists(_) | WorldError::DefaultWorld => StatusCode::CONFLICT,
+        WorldError::InvalidId(_) | WorldError::Config(_) | WorldError::SharedFields(_) => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        WorldError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+/// The world named by the `world_id` path parameter, or the default world on
+/// routes without one
+#[async_trait]
+impl FromRequestParts<AppState> for WorldHandle {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let params = UrlPath::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map(|UrlPath(params)| params)
+            .unwrap_or_default();
+        let id = params.get("world_id").map(String::as_str).unwrap_or(DEFAULT_WORLD);
+
+        state
+            .registry
+            .get(id)
+            .ok_or_else(|| world_error(WorldError::NotFound(id.to_string())))
+    }
+}
+
+/// GET /api/worlds: every world, ordered by id
+pub async fn list_handler(AxumState(state): AxumState<AppState>) -> Json<Vec<WorldInfo>> {
+    let mut worlds = Vec::new();
+    for world in state.registry.list() {
+        worlds.push(WorldInfo::of(&world).await);
+    }
+    Json(worlds)
+}
+
+/// GET /api/worlds/{world_id}
+pub async fn get_handler(world: WorldHandle) -> Json<WorldInfo> {
+    Json(WorldInfo::of(&world).await)
+}
+
+/// POST /api/worlds: start a new world with a fresh state
+pub async fn create_handler(
+    AxumState(state): AxumState<AppState>,
+    Json(request): Json<WorldRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let world = state
+        .registry
+        .create(request.id, request.config.as_ref())
+        .map_err(world_error)?;
+    Ok((StatusCode::CREATED, Json(WorldInfo::of(&world).await)))
+}
+
+/// POST /api/worlds/{world_id}/clone: start a new world from a copy of this one
+pub async fn clone_handler(
+    AxumState(state): AxumState<AppState>,
+    world: WorldHandle,
+    Json(request): Json<WorldRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let clone = state
+        .registry
+        .clone_world(&world.id, request.id, request.config.as_ref())
+        .await
+        .map_err(world_error)?;
+    Ok((StatusCode::CREATED, Json(WorldInfo::of(&clone).await)))
+}
+
+/// DELETE /api/worlds/{world_id}: stop the world and delete its checkpoints
+pub async fn delete_handler(AxumState(state): AxumState<AppState>, world: WorldHandle) -> Result<StatusCode, ApiError> {
+    state.registry.remove(&world.id).await.map_err(world_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::TestServer;
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_api_routes_reach_the_default_world() {
+        let server = TestServer::start("worlds_default", |_| {});
+
+        let (status, created) = server
+            .send(
+                Method::POST,
+                "/api/worlds",
+                Some(json!({ "id": "other", "config": { "evolution": { "mutation_rate": 0.2 } } })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["id"], "other");
+
+        let (status, _) = server.send(Method::POST, "/api/worlds/other/simulation/pause", None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Routes without a world id use the default world, like its nested
+        // routes, and never the other one
+        for prefix in ["/api", "/api/worlds/default"] {
+            let (_, run_state) = server.send(Method::GET, &format!("{}/simulation", prefix), None).await;
+            assert_eq!(run_state["paused"], false, "{}", prefix);
+            let (_, config) = server.send(Method::GET, &format!("{}/config", prefix), None).await;
+            assert_eq!(config["evolution"]["mutation_rate"], server.world.config.get().evolution.mutation_rate);
+        }
+        let (_, run_state) = server.send(Method::GET, "/api/worlds/other/simulation", None).await;
+        assert_eq!(run_state["paused"], true);
+        let (_, config) = server.send(Method::GET, "/api/worlds/other/config", None).await;
+        assert_eq!(config["evolution"]["mutation_rate"], 0.2);
+
+        let (status, _) = server.send(Method::DELETE, "/api/worlds/other", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_unknown_world_is_not_found() {
+        let server = TestServer::start("worlds_unknown", |_| {});
+
+        for (method, uri) in [
+            (Method::GET, "/api/worlds/missing"),
+            (Method::DELETE, "/api/worlds/missing"),
+            (Method::GET, "/api/worlds/missing/simulation"),
+            (Method::POST, "/api/worlds/missing/simulation/pause"),
+            (Method::GET, "/api/worlds/missing/config"),
+            (Method::GET, "/api/worlds/missing/checkpoints"),
+            (Method::POST, "/api/worlds/missing/checkpoints"),
+        ] {
+            let (status, body) = server.send(method.clone(), uri, None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, uri);
+            assert!(body.as_str().unwrap().contains("missing"), "{} {}", method, uri);
+        }
+
+        server.stop().await;
+    }
+}
@@ -0,0 +1,162 @@
+use tokio::sync::watch;
+
+/// Why the process is shutting down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// SIGINT or SIGTERM
+    Signal,
+    /// Restart requested through the API
+    Restart,
+    /// The simulation stopped by itself, e.g. because every creature died
+    SimulationEnded,
+    /// A single world was deleted through the API; only that world's tick
+    /// loop and connections stop
+    Deleted,
+}
+
+/// Shared shutdown flag. The tick loop, HTTP server and every WebSocket
+/// connection hold a clone and wind down once it is triggered.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    sender: watch::Sender<Option<ShutdownReason>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(None);
+        Self { sender }
+    }
+
+    /// Request shutdown. Only the first reason is kept.
+    pub fn trigger(&self, reason: ShutdownReason) {
+        self.sender.send_if_modified(|current| {
+            if current.is_some() {
+                return false;
+            }
+            *current = Some(reason);
+            true
+        });
+    }
+
+    pub fn reason(&self) -> Option<ShutdownReason> {
+        *self.sender.borrow()
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.reason().is_some()
+    }
+
+    /// Resolves once shutdown has been requested
+    pub async fn wait(&self) -> ShutdownReason {
+        let mut receiver = self.sender.subscribe();
+        let reason = receiver
+            .wait_for(|reason| reason.is_some())
+            .await
+            .expect("shutdown sender is owned by self");
+        reason.expect("wait_for only returns once a reason is set")
+    }
+
+    /// A shutdown flag that is triggered along with this one, with the same
+    /// reason, but can also be triggered on its own
+    pub fn child(&self) -> Shutdown {
+        let child = Shutdown::new();
+        let parent = self.clone();
+        let linked = child.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                reason = parent.wait() => linked.trigger(reason),
+                _ = linked.wait() => {}
+            }
+        });
+        child
+    }
+
+    /// Trigger shutdown on SIGINT or SIGTERM. A second signal while shutting
+    /// down exits immediately.
+    pub fn listen_for_signals(&self) {
+        let shutdown = self.clone();
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            log::warn!("Shutdown signal received, finishing the current tick and saving a checkpoint");
+            shutdown.trigger(ShutdownReason::Signal);
+
+            wait_for_signal().await;
+            log::warn!("Second shutdown signal received, exiting immediately");
+            std::process::exit(1);
+        });
+    }
+}
+
+async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                log::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_first_reason_wins() {
+        let shutdown = Shutdown::new();
+        assert!(!shutdown.is_triggered());
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.wait().await }
+        });
+
+        shutdown.trigger(ShutdownReason::Restart);
+        shutdown.trigger(ShutdownReason::Signal);
+
+        assert_eq!(waiter.await.unwrap(), ShutdownReason::Restart);
+        assert_eq!(shutdown.wait().await, ShutdownReason::Restart);
+    }
+
+    #[tokio::test]
+    async fn test_child_follows_parent() {
+        let parent = Shutdown::new();
+        let deleted = parent.child();
+        let running = parent.child();
+
+        deleted.trigger(ShutdownReason::Deleted);
+        assert!(!parent.is_triggered());
+        assert!(!running.is_triggered());
+
+        parent.trigger(ShutdownReason::Signal);
+        assert_eq!(running.wait().await, ShutdownReason::Signal);
+        assert_eq!(deleted.reason(), Some(ShutdownReason::Deleted));
+    }
+}
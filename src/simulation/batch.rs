@@ -0,0 +1,183 @@
+use super::SimulationState;
+use crate::config::Config;
+use crate::stats::export::MetricsWriter;
+use crate::stats::SimulationMetrics;
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+const PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// When an unpaced batch run stops, besides extinction
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    /// Number of ticks to run
+    pub max_ticks: Option<u64>,
+    /// Stop once any creature reaches this generation
+    pub target_generation: Option<u64>,
+    /// Write a metrics sample every this many ticks
+    pub metrics_interval: u64,
+}
+
+/// Why a batch run stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    TickLimit,
+    Extinction,
+    TargetGeneration,
+    /// `should_stop` asked to stop, e.g. on Ctrl+C
+    Interrupted,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            StopReason::TickLimit => "tick limit reached",
+            StopReason::Extinction => "all creatures died",
+            StopReason::TargetGeneration => "target generation reached",
+            StopReason::Interrupted => "interrupted",
+        };
+        f.write_str(reason)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchSummary {
+    pub reason: StopReason,
+    pub ticks_run: u64,
+    pub elapsed_seconds: f64,
+    pub final_metrics: SimulationMetrics,
+}
+
+/// Tick `state` as fast as possible until a stop condition is met, writing
+/// metrics every `metrics_interval` ticks and once more at the end.
+/// `should_stop` is polled between ticks.
+pub fn run_batch(
+    state: &mut SimulationState,
+    config: &Config,
+    options: &BatchOptions,
+    mut metrics: Option<&mut MetricsWriter>,
+    should_stop: impl Fn() -> bool,
+) -> Result<BatchSummary, Box<dyn std::error::Error>> {
+    let start_tick = state.tick;
+    let started = Instant::now();
+    let mut last_progress = started;
+    let interval = options.metrics_interval.max(1);
+
+    if let Some(writer) = metrics.as_deref_mut() {
+        writer.write(&state.metrics())?;
+    }
+
+    let reason = loop {
+        if state.creatures.is_empty() {
+            break StopReason::Extinction;
+        }
+        if options
+            .target_generation
+            .is_some_and(|target| state.max_generation() >= target)
+        {
+            break StopReason::TargetGeneration;
+        }
+        if options.max_ticks.is_some_and(|max| state.tick - start_tick >= max) {
+            break StopReason::TickLimit;
+        }
+        if should_stop() {
+            break StopReason::Interrupted;
+        }
+
+        state.tick(config);
+
+        if (state.tick - start_tick).is_multiple_of(interval) {
+            if let Some(writer) = metrics.as_deref_mut() {
+                writer.write(&state.metrics())?;
+            }
+        }
+
+        if last_progress.elapsed() >= PROGRESS_LOG_INTERVAL {
+            let ticks_run = state.tick - start_tick;
+            log::info!(
+                "Tick: {} | Population: {} | {:.0} ticks/s",
+                state.tick,
+                state.creatures.len(),
+                ticks_run as f64 / started.elapsed().as_secs_f64()
+            );
+            last_progress = Instant::now();
+        }
+    };
+
+    let final_metrics = state.metrics();
+    if let Some(writer) = metrics {
+        if !(state.tick - start_tick).is_multiple_of(interval) {
+            writer.write(&final_metrics)?;
+        }
+        writer.flush()?;
+    }
+
+    Ok(BatchSummary {
+        reason,
+        ticks_run: state.tick - start_tick,
+        elapsed_seconds: started.elapsed().as_secs_f64(),
+        final_metrics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::export::MetricsFormat;
+
+    fn small_config() -> Config {
+        let mut config = Config::default();
+        config.world.width = 30;
+        config.world.height = 30;
+        config.creature.initial_population = 20;
+        config.simulation.seed = Some(3);
+        config
+    }
+
+    #[test]
+    fn test_stops_at_tick_limit_and_writes_metrics() {
+        let mut config = small_config();
+        config.schedule = serde_json::from_str(
+            r#"{ "evolution.mutation_rate": { "type": "piecewise", "points": [[0, 0.01], [50, 0.02]] } }"#,
+        )
+        .unwrap();
+        let mut state = SimulationState::new(&config);
+        let path = std::env::temp_dir().join(format!("evo_batch_metrics_{}.csv", std::process::id()));
+        let mut writer = MetricsWriter::create(&path, MetricsFormat::Csv).unwrap();
+
+        let options = BatchOptions {
+            max_ticks: Some(25),
+            target_generation: None,
+            metrics_interval: 10,
+        };
+        let summary = run_batch(&mut state, &config, &options, Some(&mut writer), || false).unwrap();
+
+        assert_eq!(summary.ticks_run, 25);
+        assert_eq!(state.tick, 25);
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("tick,population,"));
+        assert!(lines[0].ends_with(",evolution.mutation_rate"));
+        // Samples at ticks 0, 10, 20 and the final tick
+        let ticks: Vec<&str> = lines[1..].iter().map(|l| l.split(',').next().unwrap()).collect();
+        assert_eq!(ticks, vec!["0", "10", "20", "25"]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_stops_on_extinction_and_interrupt() {
+        let config = small_config();
+        let mut state = SimulationState::new(&config);
+        state.creatures.clear();
+        let summary = run_batch(&mut state, &config, &BatchOptions::default(), None, || false).unwrap();
+        assert_eq!(summary.reason, StopReason::Extinction);
+
+        let mut state = SimulationState::new(&config);
+        let summary = run_batch(&mut state, &config, &BatchOptions::default(), None, || true).unwrap();
+        assert_eq!(summary.reason, StopReason::Interrupted);
+        assert_eq!(summary.ticks_run, 0);
+    }
+}
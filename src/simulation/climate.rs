@@ -0,0 +1,130 @@
+use super::SimulationState;
+use crate::config::{ClimateConfig, Config};
+use std::f64::consts::TAU;
+
+/// Where the world is in its seasonal and day/night cycles. It is worked out
+/// from the tick, so it needs no state of its own and survives checkpoints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Climate {
+    /// Fraction of the year gone, from the start of spring. None without
+    /// seasons.
+    season_phase: Option<f64>,
+    /// Fraction of the day gone, from dawn. None without days.
+    day_phase: Option<f64>,
+    food_amplitude: f64,
+    metabolism_amplitude: f64,
+    night_sensor_range: f64,
+}
+
+impl Climate {
+    pub fn at(tick: u64, config: &ClimateConfig) -> Self {
+        let phase = |length: u64| (length > 0).then(|| (tick % length) as f64 / length as f64);
+        Self {
+            season_phase: phase(config.season_length_ticks),
+            day_phase: phase(config.day_length_ticks),
+            food_amplitude: config.season_food_amplitude,
+            metabolism_amplitude: config.season_metabolism_amplitude,
+            night_sensor_range: config.night_sensor_range,
+        }
+    }
+
+    /// Fraction of the year gone, 0 without seasons
+    pub fn season_phase(&self) -> f64 {
+        self.season_phase.unwrap_or(0.0)
+    }
+
+    /// Fraction of the day gone, 0 without days
+    pub fn day_phase(&self) -> f64 {
+        self.day_phase.unwrap_or(0.0)
+    }
+
+    /// 1 at midsummer, 0 at midwinter and 0.5 at the equinoxes or without
+    /// seasons
+    pub fn warmth(&self) -> f64 {
+        (1.0 + self.season_level()) / 2.0
+    }
+
+    /// 1 at noon, 0 at midnight and 0.5 at dawn and dusk. Always 1 without
+    /// days.
+    pub fn daylight(&self) -> f64 {
+        self.day_phase
+            .map_or(1.0, |phase| (1.0 + (TAU * phase).sin()) / 2.0)
+    }
+
+    /// Factor on food regrowth: highest in summer, lowest in winter
+    pub fn food_regen_multiplier(&self) -> f64 {
+        1.0 + self.food_amplitude * self.season_level()
+    }
+
+    /// Factor on the per-tick energy cost: highest in winter, lowest in
+    /// summer
+    pub fn metabolism_multiplier(&self) -> f64 {
+        1.0 - self.metabolism_amplitude * self.season_level()
+    }
+
+    /// Factor on how far creatures sense: 1 in full daylight, down to
+    /// `night_sensor_range` at midnight
+    pub fn sensor_range(&self) -> f64 {
+        self.night_sensor_range + (1.0 - self.night_sensor_range) * self.daylight()
+    }
+
+    /// Sine of the year, from -1 in midwinter to 1 in midsummer
+    fn season_level(&self) -> f64 {
+        self.season_phase.map_or(0.0, |phase| (TAU * phase).sin())
+    }
+}
+
+impl SimulationState {
+    /// The climate at the current tick
+    pub fn climate(&self, config: &Config) -> Climate {
+        Climate::at(self.tick, &config.world.climate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seasons_and_days() {
+        let config = ClimateConfig {
+            season_length_ticks: 1000,
+            day_length_ticks: 200,
+            season_food_amplitude: 0.5,
+            season_metabolism_amplitude: 0.2,
+            night_sensor_range: 0.4,
+        };
+
+        let spring = Climate::at(0, &config);
+        assert_eq!(spring.warmth(), 0.5);
+        assert_eq!(spring.food_regen_multiplier(), 1.0);
+        assert_eq!(spring.daylight(), 0.5);
+
+        // Midsummer noon, in the second year
+        let summer = Climate::at(1250, &config);
+        assert_eq!(summer.season_phase(), 0.25);
+        assert_eq!(summer.day_phase(), 0.25);
+        assert!((summer.warmth() - 1.0).abs() < 1e-12);
+        assert!((summer.daylight() - 1.0).abs() < 1e-12);
+        assert!((summer.sensor_range() - 1.0).abs() < 1e-12);
+        assert!((summer.food_regen_multiplier() - 1.5).abs() < 1e-12);
+        assert!((summer.metabolism_multiplier() - 0.8).abs() < 1e-12);
+
+        // Midwinter midnight
+        let winter = Climate::at(750, &config);
+        assert!(winter.warmth().abs() < 1e-12);
+        assert!((winter.food_regen_multiplier() - 0.5).abs() < 1e-12);
+        assert!((winter.metabolism_multiplier() - 1.2).abs() < 1e-12);
+        assert!((winter.sensor_range() - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_disabled_cycles_are_neutral() {
+        let climate = Climate::at(12_345, &ClimateConfig::default());
+        assert_eq!(climate.season_phase(), 0.0);
+        assert_eq!(climate.day_phase(), 0.0);
+        assert_eq!(climate.food_regen_multiplier(), 1.0);
+        assert_eq!(climate.metabolism_multiplier(), 1.0);
+        assert_eq!(climate.sensor_range(), 1.0);
+    }
+}
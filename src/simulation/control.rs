@@ -0,0 +1,125 @@
+use super::SimulationState;
+use crate::config::reload::{ConfigUpdate, ConfigUpdateError};
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::sync::{mpsc, oneshot, watch};
+
+/// Whether the tick loop is advancing, and how fast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunState {
+    pub paused: bool,
+    pub ticks_per_second: u64,
+    /// Ticks still to run from a step request before pausing again
+    pub pending_steps: u64,
+}
+
+impl RunState {
+    pub fn running(ticks_per_second: u64) -> Self {
+        Self {
+            paused: false,
+            ticks_per_second,
+            pending_steps: 0,
+        }
+    }
+
+    /// Whether the next tick of the pacing timer should advance the world,
+    /// using up a pending step if paused
+    pub fn take_tick(&mut self) -> bool {
+        if !self.paused {
+            return true;
+        }
+        if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            return true;
+        }
+        false
+    }
+
+    pub fn apply(&mut self, control: RunControl) {
+        match control {
+            RunControl::Pause => {
+                self.paused = true;
+                self.pending_steps = 0;
+            }
+            RunControl::Resume => {
+                self.paused = false;
+                self.pending_steps = 0;
+            }
+            RunControl::Step { ticks } => {
+                self.paused = true;
+                self.pending_steps = self.pending_steps.saturating_add(ticks);
+            }
+        }
+    }
+}
+
+/// Pause, resume or single-step the tick loop. Speed is changed through the
+/// config's `simulation.ticks_per_second`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunControl {
+    Pause,
+    Resume,
+    /// Run `ticks` more ticks at the current speed, then stay paused
+    Step { ticks: u64 },
+}
+
+/// Requests to the tick loop, applied between ticks
+#[derive(Debug)]
+pub enum SimulationCommand {
+    /// Replace the running simulation with a state loaded from a checkpoint.
+    /// `done` receives the tick the simulation resumed at.
+    Load {
+        state: Box<SimulationState>,
+        source: PathBuf,
+        done: oneshot::Sender<u64>,
+    },
+    /// Switch to a new config. `done` receives the fields that changed, or
+    /// why the config was rejected; nothing is applied if it is rejected.
+    UpdateConfig {
+        config: Box<Config>,
+        /// Where the change came from, for the log
+        source: String,
+        done: oneshot::Sender<Result<ConfigUpdate, ConfigUpdateError>>,
+    },
+    /// Change the run state. `done` receives the state after the change.
+    Control {
+        control: RunControl,
+        done: oneshot::Sender<RunState>,
+    },
+}
+
+pub type CommandSender = mpsc::UnboundedSender<SimulationCommand>;
+pub type CommandReceiver = mpsc::UnboundedReceiver<SimulationCommand>;
+
+pub fn channel() -> (CommandSender, CommandReceiver) {
+    mpsc::unbounded_channel()
+}
+
+/// The tick loop publishes its run state here for every connected client
+pub type RunStateSender = watch::Sender<RunState>;
+pub type RunStateReceiver = watch::Receiver<RunState>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_while_paused() {
+        let mut run_state = RunState::running(30);
+        assert!(run_state.take_tick());
+
+        run_state.apply(RunControl::Pause);
+        assert!(!run_state.take_tick());
+
+        run_state.apply(RunControl::Step { ticks: 2 });
+        assert!(run_state.take_tick());
+        assert!(run_state.take_tick());
+        assert!(!run_state.take_tick());
+        assert!(run_state.paused);
+
+        run_state.apply(RunControl::Step { ticks: 5 });
+        run_state.apply(RunControl::Resume);
+        assert_eq!(run_state, RunState::running(30));
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+/// Something that happened to the world during a tick
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SimEvent {
+    Birth {
+        id: u64,
+        parent_id: u64,
+        x: usize,
+        y: usize,
+        generation: u64,
+    },
+    /// Creature re-created from a dead creature's genome by the extinction failsafe
+    Resurrection {
+        id: u64,
+        source_id: u64,
+        x: usize,
+        y: usize,
+    },
+    Death {
+        id: u64,
+        x: usize,
+        y: usize,
+        age: u64,
+    },
+    Move {
+        id: u64,
+        x: usize,
+        y: usize,
+    },
+    Attack {
+        attacker_id: u64,
+        target_id: u64,
+        damage: f64,
+    },
+    Share {
+        giver_id: u64,
+        receiver_id: u64,
+        amount: f64,
+    },
+    FoodSpawn {
+        x: usize,
+        y: usize,
+        amount: u32,
+        is_meat: bool,
+    },
+    /// Food of the other kind dropped on a cell replaced what was there
+    FoodReplaced {
+        x: usize,
+        y: usize,
+        amount: u32,
+        is_meat: bool,
+    },
+}
+
+/// Per-tick event buffer. Recording is off by default so ticks that nobody
+/// journals don't pay for it.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    enabled: bool,
+    events: Vec<SimEvent>,
+}
+
+impl EventLog {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.events.clear();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[inline]
+    pub fn record(&mut self, event: SimEvent) {
+        if self.enabled {
+            self.events.push(event);
+        }
+    }
+
+    /// Take the events recorded since the last call
+    pub fn take(&mut self) -> Vec<SimEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
@@ -1,18 +1,29 @@
+pub mod batch;
+pub mod climate;
+pub mod control;
+pub mod events;
+pub mod registry;
+pub mod rng;
+pub mod runner;
+pub mod sweep;
 pub mod tick;
 
 use crate::config::Config;
 use crate::creature::{genome::Genome, Creature};
+use crate::simulation::events::EventLog;
+use crate::simulation::rng::SimRng;
 use crate::simulation::tick::Direction;
 use crate::stats::SimulationMetrics;
-use crate::world::World;
+use crate::world::{biome, BiomeMap, Topology, World, WorldMaps};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SpatialIndex {
     width: usize,
     height: usize,
+    topology: Topology,
     cells: Vec<Option<u64>>,
 }
 
@@ -21,10 +32,16 @@ impl SpatialIndex {
         Self {
             width,
             height,
+            topology: Topology::Bounded,
             cells: vec![None; width * height],
         }
     }
 
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
     #[inline]
     fn idx(&self, x: usize, y: usize) -> usize {
         y * self.width + x
@@ -51,23 +68,28 @@ impl SpatialIndex {
         self.cells.fill(None);
     }
 
-    pub fn iter_box(
-        &self,
-        x_min: usize,
-        y_min: usize,
-        x_max: usize,
-        y_max: usize,
-    ) -> BoundingBoxIter<'_> {
+    /// Creatures in the inclusive box, row by row. Bounds may lie past the
+    /// edges: a bounded index clips the box, a torus wraps it around.
+    pub fn iter_box(&self, x_min: i64, y_min: i64, x_max: i64, y_max: i64) -> BoundingBoxIter<'_> {
+        let (x_start, columns) = self.topology.span(x_min, x_max, self.width);
+        let (y_start, rows) = self.topology.span(y_min, y_max, self.height);
         BoundingBoxIter {
             index: self,
-            x: x_min,
-            y: y_min,
-            x_min,
-            x_max,
-            y_max,
+            x_start,
+            y_start,
+            columns,
+            rows,
+            column: 0,
+            row: 0,
         }
     }
 
+    /// Creatures at most `radius` cells from `(x, y)` along each axis
+    pub fn iter_around(&self, x: usize, y: usize, radius: usize) -> BoundingBoxIter<'_> {
+        let (x, y, radius) = (x as i64, y as i64, radius as i64);
+        self.iter_box(x - radius, y - radius, x + radius, y + radius)
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -77,36 +99,28 @@ impl SpatialIndex {
     }
 }
 
-impl Default for SpatialIndex {
-    fn default() -> Self {
-        Self {
-            width: 0,
-            height: 0,
-            cells: Vec::new(),
-        }
-    }
-}
-
 pub struct BoundingBoxIter<'a> {
     index: &'a SpatialIndex,
-    x: usize,
-    y: usize,
-    x_min: usize,
-    x_max: usize,
-    y_max: usize,
+    x_start: usize,
+    y_start: usize,
+    columns: usize,
+    rows: usize,
+    column: usize,
+    row: usize,
 }
 
 impl<'a> Iterator for BoundingBoxIter<'a> {
     type Item = (usize, usize, u64);
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.y <= self.y_max {
-            if let Some(id) = self.index.get(self.x, self.y) {
-                let result = (self.x, self.y, id);
-                self.advance();
-                return Some(result);
-            }
+        while self.row < self.rows && self.columns > 0 {
+            // Spans never exceed the grid, so one wrap is enough
+            let x = (self.x_start + self.column) % self.index.width;
+            let y = (self.y_start + self.row) % self.index.height;
             self.advance();
+            if let Some(id) = self.index.get(x, y) {
+                return Some((x, y, id));
+            }
         }
         None
     }
@@ -114,16 +128,10 @@ impl<'a> Iterator for BoundingBoxIter<'a> {
 
 impl<'a> BoundingBoxIter<'a> {
     fn advance(&mut self) {
-        if self.x >= self.x_max {
-            self.x = self.x_min;
-            if self.y >= self.y_max {
-                // Move beyond bounds to signal completion
-                self.y = self.y_max + 1;
-            } else {
-                self.y += 1;
-            }
-        } else {
-            self.x += 1;
+        self.column += 1;
+        if self.column >= self.columns {
+            self.column = 0;
+            self.row += 1;
         }
     }
 }
@@ -134,10 +142,24 @@ pub struct SimulationState {
     pub creatures: HashMap<u64, Creature>,
     #[serde(skip)]
     pub creature_positions: SpatialIndex,
-    #[serde(skip)]
+    /// Attacks made last tick, read by the attack sensors
+    #[serde(default)]
     pub attacks_last_tick: HashMap<u64, Vec<Direction>>,
-    #[serde(skip)]
+    /// Genomes the extinction failsafe resurrects from
+    #[serde(default)]
     pub recently_dead: VecDeque<Creature>,
+    /// Seed the run was started with; together with the config it fully
+    /// determines the tick history
+    #[serde(default)]
+    pub seed: u64,
+    pub rng: SimRng,
+    /// Events emitted by the current tick, when recording is enabled
+    #[serde(skip)]
+    pub events: EventLog,
+    /// Values of scheduled config fields in the last tick, or the next one
+    /// before the first, by path
+    #[serde(skip)]
+    pub scheduled_values: BTreeMap<String, f64>,
     pub tick: u64,
     pub next_creature_id: u64,
     pub total_births: u64,
@@ -145,22 +167,40 @@ pub struct SimulationState {
 }
 
 impl SimulationState {
+    /// A fresh world, reading the map files the config names. If one can't
+    /// be used the world starts without it.
     pub fn new(config: &Config) -> Self {
-        let mut world = World::new(config.world.width, config.world.height);
-        world.initialize_food(
-            config.world.initial_food_density,
-            config.world.max_food_per_cell,
-        );
+        let maps = config.load_maps().unwrap_or_else(|errors| {
+            for error in &errors.0 {
+                log::error!("{}; starting without this map", error);
+            }
+            WorldMaps::default()
+        });
+        Self::with_maps(config, &maps)
+    }
+
+    /// A fresh world laid out from maps already read with `Config::load_maps`
+    pub fn with_maps(config: &Config, maps: &WorldMaps) -> Self {
+        let seed = rng::resolve_seed(config.simulation.seed);
+        log::info!("Simulation seed: {}", seed);
+        let mut rng = rng::state_rng(seed);
+
+        let mut world =
+            World::new(config.world.width, config.world.height).with_topology(config.world.topology);
+        if let Some(map) = &maps.terrain {
+            world.apply_terrain(map);
+        }
+        Self::lay_out_biomes(&mut world, config, seed, maps.biomes.as_ref());
+        world.initialize_food(&config.world.fertility(), &mut rng);
 
         let mut creatures = HashMap::new();
-        let mut creature_positions = SpatialIndex::new(config.world.width, config.world.height);
-        let mut rng = rand::thread_rng();
+        let mut creature_positions = SpatialIndex::new(config.world.width, config.world.height)
+            .with_topology(config.world.topology);
 
         for id in 0..config.creature.initial_population {
-            let x = rng.gen_range(0..config.world.width);
-            let y = rng.gen_range(0..config.world.height);
+            let (x, y) = world.random_open_cell(&mut rng);
 
-            let genome = Genome::random(config.evolution.genome_size);
+            let genome = Genome::random(config.evolution.genome_size, &mut rng);
             let creature = Creature::new(
                 id as u64,
                 x,
@@ -179,25 +219,53 @@ impl SimulationState {
             creatures.insert(id as u64, creature);
         }
 
-        Self::apply_population_cap(&mut creatures, config.creature.max_population);
+        Self::apply_population_cap(&mut creatures, config.creature.max_population, &mut rng);
 
         // Rebuild position index after population cap
-        let mut creature_positions = SpatialIndex::new(config.world.width, config.world.height);
-        for (id, creature) in &creatures {
-            creature_positions.set(creature.x, creature.y, *id);
+        let mut creature_positions = SpatialIndex::new(config.world.width, config.world.height)
+            .with_topology(config.world.topology);
+        // Creatures can spawn on the same cell; set them in id order so the
+        // same one owns it in every run
+        let mut ids: Vec<u64> = creatures.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            creature_positions.set(creatures[&id].x, creatures[&id].y, id);
         }
 
-        Self {
+        let mut state = Self {
             world,
             creatures,
             creature_positions,
             attacks_last_tick: HashMap::new(),
             recently_dead: VecDeque::new(),
+            seed,
+            rng,
+            events: EventLog::default(),
+            scheduled_values: BTreeMap::new(),
             tick: 0,
             next_creature_id: config.creature.initial_population as u64,
             total_births: 0,
             total_deaths: 0,
+        };
+        state.update_scheduled_values(config);
+        state
+    }
+
+    /// Divide a new world between the configured biomes, from their map or
+    /// from noise
+    fn lay_out_biomes(world: &mut World, config: &Config, seed: u64, map: Option<&BiomeMap>) {
+        let biomes = &config.world.biomes;
+        if biomes.kinds.is_empty() {
+            return;
         }
+
+        let (width, height, kinds) = (world.width(), world.height(), biomes.kinds.len());
+        let layout = match map {
+            Some(map) => map.stretched(width, height),
+            None => biome::noise_layout(width, height, kinds, biomes.noise_scale, biomes.seed.unwrap_or(seed)),
+        };
+        let names = biomes.kinds.iter().map(|kind| kind.name.clone()).collect();
+        world.set_biomes(layout, names);
     }
 
     pub fn creatures_vec(&self) -> Vec<Creature> {
@@ -207,27 +275,45 @@ impl SimulationState {
     pub fn metrics(&self) -> SimulationMetrics {
         let creatures = self.creatures_vec();
         let total_food = self.world.total_food();
-        SimulationMetrics::compute(
+        let mut metrics = SimulationMetrics::compute(
             self.tick,
             &creatures,
             total_food,
             self.total_births,
             self.total_deaths,
-        )
+        );
+        metrics.scheduled = self.scheduled_values.clone();
+        metrics.biome_food = self
+            .world
+            .biome_names()
+            .iter()
+            .cloned()
+            .zip(self.world.food_by_biome())
+            .collect();
+        metrics
+    }
+
+    pub fn max_generation(&self) -> u64 {
+        self.creatures.values().map(|c| c.genome.generation).max().unwrap_or(0)
     }
 
-    pub fn apply_population_cap(creatures: &mut HashMap<u64, Creature>, max_population: usize) {
+    pub fn apply_population_cap<R: Rng + ?Sized>(
+        creatures: &mut HashMap<u64, Creature>,
+        max_population: usize,
+        rng: &mut R,
+    ) {
         if max_population == 0 || creatures.len() <= max_population {
             return;
         }
 
         let to_remove = creatures.len() - max_population;
-        let mut rng = rand::thread_rng();
-        let creature_ids: Vec<u64> = creatures.keys().copied().collect();
+        let mut creature_ids: Vec<u64> = creatures.keys().copied().collect();
+        // HashMap iteration order differs between processes
+        creature_ids.sort_unstable();
 
         use rand::seq::SliceRandom;
         let mut ids_to_remove = creature_ids;
-        ids_to_remove.shuffle(&mut rng);
+        ids_to_remove.shuffle(rng);
 
         for &id in ids_to_remove.iter().take(to_remove) {
             creatures.remove(&id);
@@ -279,11 +365,51 @@ impl SimulationState {
         }
     }
 
+    /// Prepare a state loaded from a checkpoint to continue under `config`
+    pub fn prepare_resume(&mut self, config: &Config) {
+        Self::apply_population_cap(
+            &mut self.creatures,
+            config.creature.max_population,
+            &mut self.rng,
+        );
+        self.world.set_topology(config.world.topology);
+        self.rebuild_spatial_index();
+        self.update_scheduled_values(config);
+
+        // Brains keep the shape they were saved with, and offspring inherit
+        // it, so sensors the checkpoint predates stay unused
+        let configured = (
+            config.evolution.neural_net_inputs,
+            config.evolution.neural_net_hidden,
+            config.evolution.neural_net_outputs,
+        );
+        if let Some(saved) = self.creatures.values().map(|c| c.brain.dimensions()).find(|&d| d != configured) {
+            log::warn!(
+                "Checkpoint brains are {:?} (inputs, hidden, outputs) but the config asks for {:?}; \
+                 keeping the checkpoint's until the next fresh start",
+                saved,
+                configured
+            );
+        }
+    }
+
+    /// Evaluate the config schedule at the current tick, for metrics
+    pub fn update_scheduled_values(&mut self, config: &Config) {
+        self.scheduled_values = config
+            .scheduled_at(self.tick)
+            .map(|(_, values)| values)
+            .unwrap_or_default();
+    }
+
     /// Rebuild spatial index from creatures (for deserialization)
     pub fn rebuild_spatial_index(&mut self) {
-        self.creature_positions = SpatialIndex::new(self.world.width(), self.world.height());
-        for (id, creature) in &self.creatures {
-            self.creature_positions.set(creature.x, creature.y, *id);
+        self.creature_positions = SpatialIndex::new(self.world.width(), self.world.height())
+            .with_topology(self.world.topology());
+        let mut ids: Vec<u64> = self.creatures.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let creature = &self.creatures[&id];
+            self.creature_positions.set(creature.x, creature.y, id);
         }
     }
 }
@@ -312,6 +438,40 @@ mod tests {
         assert_eq!(metrics.population, config.creature.initial_population);
     }
 
+    #[test]
+    fn test_biomes_from_noise() {
+        use crate::config::BiomeKind;
+
+        let mut config = Config::default();
+        config.world.width = 60;
+        config.world.height = 60;
+        config.world.biomes.kinds = vec![
+            BiomeKind {
+                name: "desert".to_string(),
+                initial_food_density: Some(0.0),
+                food_regen_rate: Some(0.0),
+                ..Default::default()
+            },
+            BiomeKind {
+                name: "meadow".to_string(),
+                initial_food_density: Some(0.5),
+                ..Default::default()
+            },
+        ];
+        config.simulation.seed = Some(3);
+        assert!(config.validate().is_ok());
+
+        let sim = SimulationState::new(&config);
+        let again = SimulationState::new(&config);
+        let layout: Vec<usize> = (0..60).map(|x| sim.world.biome(x, 30)).collect();
+        assert_eq!(layout, (0..60).map(|x| again.world.biome(x, 30)).collect::<Vec<_>>());
+
+        let metrics = sim.metrics();
+        assert_eq!(metrics.biome_food["desert"], 0);
+        assert!(metrics.biome_food["meadow"] > 0);
+        assert_eq!(metrics.biome_food["meadow"], metrics.total_food);
+    }
+
     #[test]
     fn test_spatial_index_basic_operations() {
         let mut index = SpatialIndex::new(4, 3);
@@ -334,4 +494,21 @@ mod tests {
         index.clear_all();
         assert!(index.iter_box(0, 0, 3, 2).next().is_none());
     }
+
+    #[test]
+    fn test_spatial_index_torus_box_wraps() {
+        let mut index = SpatialIndex::new(4, 3).with_topology(Topology::Torus);
+        index.set(3, 2, 1);
+        index.set(1, 1, 2);
+
+        let collected: Vec<_> = index.iter_box(-1, -1, 0, 0).collect();
+        assert_eq!(collected, vec![(3, 2, 1)]);
+
+        // A box wider than the grid visits each cell once
+        assert_eq!(index.iter_around(0, 0, 10).count(), 2);
+
+        let mut bounded = SpatialIndex::new(4, 3);
+        bounded.set(3, 2, 1);
+        assert!(bounded.iter_box(-1, -1, 0, 0).next().is_none());
+    }
 }
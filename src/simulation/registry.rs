@@ -0,0 +1,443 @@
+use super::control::{self, CommandSender, RunState, RunStateReceiver};
+use super::runner::run_simulation;
+use super::SimulationState;
+use crate::checkpoint;
+use crate::config::reload::{ConfigUpdateError, LiveConfig};
+use crate::config::Config;
+use crate::shutdown::{Shutdown, ShutdownReason};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinHandle;
+
+/// Id of the world started from the config file. It can't be deleted, and
+/// the server stops when its simulation ends.
+pub const DEFAULT_WORLD: &str = "default";
+
+const MAX_ID_LEN: usize = 64;
+
+/// A running simulation and the handles used to reach its tick loop
+#[derive(Clone)]
+pub struct WorldHandle {
+    pub id: String,
+    pub state: Arc<RwLock<SimulationState>>,
+    pub config: LiveConfig,
+    pub commands: CommandSender,
+    pub run_state: RunStateReceiver,
+    /// Triggered when the server shuts down or the world is deleted
+    pub shutdown: Shutdown,
+}
+
+impl WorldHandle {
+    /// Whether the tick loop is still running. It stops once every creature
+    /// has died.
+    pub fn is_running(&self) -> bool {
+        !self.commands.is_closed()
+    }
+
+    /// A copy of the current state, so readers don't hold up the tick loop
+    pub async fn snapshot(&self) -> SimulationState {
+        self.state.read().await.clone()
+    }
+}
+
+/// Why a world could not be created, cloned or deleted
+#[derive(Debug)]
+pub enum WorldError {
+    NotFound(String),
+    AlreadyExists(String),
+    InvalidId(String),
+    /// The default world is tied to the config file and the server process
+    DefaultWorld,
+    /// The world's config is invalid, or can't be applied to a cloned state
+    Config(ConfigUpdateError),
+    /// Fields every world shares with the server
+    SharedFields(Vec<String>),
+    Io(String),
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::NotFound(id) => write!(f, "No world named {}", id),
+            WorldError::AlreadyExists(id) => write!(f, "A world named {} already exists", id),
+            WorldError::InvalidId(id) => write!(
+                f,
+                "Invalid world id {:?}: use 1 to {} letters, digits, '-' or '_'",
+                id, MAX_ID_LEN
+            ),
+            WorldError::DefaultWorld => write!(f, "The {} world can't be deleted", DEFAULT_WORLD),
+            WorldError::Config(e) => write!(f, "{}", e),
+            WorldError::SharedFields(paths) => write!(
+                f,
+                "Shared by every world and set in the server's config: {}",
+                paths.join(", ")
+            ),
+            WorldError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Every simulation hosted by the server, each with its own config, tick
+/// loop and checkpoint directory
+#[derive(Clone)]
+pub struct WorldRegistry {
+    worlds: Arc<Mutex<BTreeMap<String, WorldHandle>>>,
+    /// Config new worlds start from. Its checkpoint directory belongs to the
+    /// default world; other worlds use `<directory>/worlds/<id>`, next to
+    /// `<id>.json` holding the config they were created with.
+    base_config: Arc<Config>,
+    shutdown: Shutdown,
+}
+
+impl WorldRegistry {
+    pub fn new(base_config: Config, shutdown: Shutdown) -> Self {
+        Self {
+            worlds: Default::default(),
+            base_config: Arc::new(base_config),
+            shutdown,
+        }
+    }
+
+    /// The config the server was started with
+    pub fn base_config(&self) -> &Config {
+        &self.base_config
+    }
+
+    pub fn get(&self, id: &str) -> Option<WorldHandle> {
+        self.worlds.lock().unwrap().get(id).cloned()
+    }
+
+    /// Every world, ordered by id
+    pub fn list(&self) -> Vec<WorldHandle> {
+        self.worlds.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Start a tick loop for `state` under `id`. The task finishes when the
+    /// loop stops, with the error that stopped it, if any.
+    pub fn start(
+        &self,
+        id: &str,
+        config: Config,
+        state: SimulationState,
+    ) -> Result<(WorldHandle, JoinHandle<Result<(), String>>), WorldError> {
+        let mut worlds = self.worlds.lock().unwrap();
+        if worlds.contains_key(id) {
+            return Err(WorldError::AlreadyExists(id.to_string()));
+        }
+
+        let (commands, command_receiver) = control::channel();
+        let (run_state, run_state_receiver) = watch::channel(RunState::running(config.simulation.ticks_per_second));
+        let world = WorldHandle {
+            id: id.to_string(),
+            state: Arc::new(RwLock::new(state)),
+            config: LiveConfig::new(config),
+            commands,
+            run_state: run_state_receiver,
+            shutdown: self.shutdown.child(),
+        };
+
+        let task = tokio::spawn({
+            let world = world.clone();
+            async move {
+                run_simulation(world.state, world.config, run_state, world.shutdown, command_receiver)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+        });
+
+        worlds.insert(id.to_string(), world.clone());
+        Ok((world, task))
+    }
+
+    /// Create a world with a fresh state. `patch` is merged into the server's
+    /// config, like `PATCH /api/config`.
+    pub fn create(&self, id: Option<String>, patch: Option<&Value>) -> Result<WorldHandle, WorldError> {
+        let id = self.new_id(id)?;
+        let mut config = match patch {
+            Some(patch) => self.base_config.patched(patch).map_err(WorldError::Config)?,
+            None => (*self.base_config).clone(),
+        };
+
+        let shared: Vec<String> = self
+            .base_config
+            .diff(&config)
+            .into_iter()
+            .map(|change| change.path)
+            .filter(|path| path.starts_with("server.") || path == "checkpoint.directory")
+            .collect();
+        if !shared.is_empty() {
+            return Err(WorldError::SharedFields(shared));
+        }
+
+        config.checkpoint.directory = self.world_directory(&id);
+        let maps = config
+            .validate()
+            .and_then(|()| config.load_maps())
+            .map_err(|e| WorldError::Config(ConfigUpdateError::Invalid(e)))?;
+
+        let state = SimulationState::with_maps(&config, &maps);
+        log::info!("Created world {} with seed {}", id, state.seed);
+        self.launch(&id, config, state)
+    }
+
+    /// Start a new world from the current state of `source`. `patch` is
+    /// applied to the source's config and may only change fields that could
+    /// be changed on a running world. Without one, the clone evolves exactly
+    /// like its source.
+    pub async fn clone_world(
+        &self,
+        source: &str,
+        id: Option<String>,
+        patch: Option<&Value>,
+    ) -> Result<WorldHandle, WorldError> {
+        let source = self
+            .get(source)
+            .ok_or_else(|| WorldError::NotFound(source.to_string()))?;
+        let id = self.new_id(id)?;
+
+        let source_config = source.config.get();
+        let mut config = match patch {
+            Some(patch) => source_config.patched(patch).map_err(WorldError::Config)?,
+            None => (*source_config).clone(),
+        };
+        source_config.live_changes(&config).map_err(WorldError::Config)?;
+        config.checkpoint.directory = self.world_directory(&id);
+
+        let mut state = source.snapshot().await;
+        // The clone records its own journal if it has one
+        state.events.set_enabled(false);
+        state.prepare_resume(&config);
+        log::info!("Cloned world {} from {} at tick {}", id, source.id, state.tick);
+        self.launch(&id, config, state)
+    }
+
+    /// Stop a world and delete its checkpoints and saved config
+    pub async fn remove(&self, id: &str) -> Result<(), WorldError> {
+        if id == DEFAULT_WORLD {
+            return Err(WorldError::DefaultWorld);
+        }
+        let world = self
+            .worlds
+            .lock()
+            .unwrap()
+            .remove(id)
+            .ok_or_else(|| WorldError::NotFound(id.to_string()))?;
+
+        world.shutdown.trigger(ShutdownReason::Deleted);
+        // Wait until the loop has stopped writing checkpoints and journals
+        world.commands.closed().await;
+
+        let directory = world.config.get().checkpoint.directory.clone();
+        if Path::new(&directory).exists() {
+            tokio::fs::remove_dir_all(&directory)
+                .await
+                .map_err(|e| WorldError::Io(format!("Failed to delete {}: {}", directory, e)))?;
+        }
+        let config_path = self.config_path(id);
+        if config_path.exists() {
+            tokio::fs::remove_file(&config_path)
+                .await
+                .map_err(|e| WorldError::Io(format!("Failed to delete {}: {}", config_path.display(), e)))?;
+        }
+        log::info!("Deleted world {}", id);
+        Ok(())
+    }
+
+    /// Start every world an earlier run created, from its latest checkpoint.
+    /// A world that can't be restored is skipped and its files left alone.
+    pub fn restore(&self) {
+        let root = Path::new(&self.base_config.checkpoint.directory).join("worlds");
+        let Ok(entries) = std::fs::read_dir(&root) else {
+            return;
+        };
+
+        let mut config_paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "json"))
+            .collect();
+        config_paths.sort();
+        for config_path in config_paths {
+            let Some(id) = config_path.file_stem().and_then(|n| n.to_str()).map(str::to_string) else {
+                continue;
+            };
+            if !is_valid_id(&id) {
+                continue;
+            }
+            if let Err(e) = self.restore_world(&id) {
+                log::error!("Not restoring world {}: {}", id, e);
+            }
+        }
+    }
+
+    fn restore_world(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = Config::load_from_file(&self.config_path(id).to_string_lossy())?;
+        config.checkpoint.directory = self.world_directory(id);
+        config.validate()?;
+
+        let state = match checkpoint::load_checkpoint(&config)? {
+            Some(mut state) => {
+                state.prepare_resume(&config);
+                state
+            }
+            None => SimulationState::with_maps(&config, &config.load_maps()?),
+        };
+        log::info!("Restored world {} at tick {}", id, state.tick);
+        self.launch(id, config, state)?;
+        Ok(())
+    }
+
+    /// Start a world that outlives the request that created it, saving its
+    /// config so it is restored on the next start
+    fn launch(&self, id: &str, config: Config, state: SimulationState) -> Result<WorldHandle, WorldError> {
+        let save_config = config.checkpoint.enabled.then(|| config.clone());
+        let (world, task) = self.start(id, config, state)?;
+
+        if let Some(config) = save_config {
+            let path = self.config_path(id);
+            let saved = std::fs::create_dir_all(path.parent().expect("config path has a parent"))
+                .map_err(|e| e.to_string())
+                .and_then(|_| config.save_to_file(&path.to_string_lossy()).map_err(|e| e.to_string()));
+            if let Err(e) = saved {
+                log::error!("Failed to save the config of world {}, it won't be restored: {}", id, e);
+            }
+        }
+
+        let id = id.to_string();
+        tokio::spawn(async move {
+            match task.await {
+                Ok(Ok(())) => log::info!("World {} stopped", id),
+                Ok(Err(e)) => log::error!("World {} stopped: {}", id, e),
+                Err(e) => log::error!("World {} crashed: {}", id, e),
+            }
+        });
+        Ok(world)
+    }
+
+    /// Resolves once every world's tick loop has stopped
+    pub async fn stopped(&self) {
+        for world in self.list() {
+            world.commands.closed().await;
+        }
+    }
+
+    /// Check a requested id, or pick the first free `world-N`
+    fn new_id(&self, requested: Option<String>) -> Result<String, WorldError> {
+        let worlds = self.worlds.lock().unwrap();
+        match requested {
+            Some(id) if !is_valid_id(&id) => Err(WorldError::InvalidId(id)),
+            Some(id) if worlds.contains_key(&id) => Err(WorldError::AlreadyExists(id)),
+            Some(id) => Ok(id),
+            None => Ok((1..)
+                .map(|n| format!("world-{}", n))
+                .find(|id| !worlds.contains_key(id))
+                .expect("some world number is free")),
+        }
+    }
+
+    fn world_directory(&self, id: &str) -> String {
+        Path::new(&self.base_config.checkpoint.directory)
+            .join("worlds")
+            .join(id)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn config_path(&self, id: &str) -> PathBuf {
+        Path::new(&self.base_config.checkpoint.directory)
+            .join("worlds")
+            .join(format!("{}.json", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(name: &str) -> (WorldRegistry, PathBuf) {
+        let directory = std::env::temp_dir().join(format!("evo_registry_{}_{}", name, std::process::id()));
+        let mut config = Config::default();
+        config.world.width = 30;
+        config.world.height = 30;
+        config.creature.initial_population = 20;
+        config.checkpoint.directory = directory.to_string_lossy().to_string();
+        config.checkpoint.journal_enabled = false;
+        (WorldRegistry::new(config, Shutdown::new()), directory)
+    }
+
+    #[tokio::test]
+    async fn test_create_clone_and_delete() {
+        let (registry, directory) = registry("lifecycle");
+
+        let world = registry
+            .create(None, Some(&serde_json::json!({ "evolution": { "mutation_rate": 0.2 } })))
+            .unwrap();
+        assert_eq!(world.id, "world-1");
+        assert_eq!(world.config.get().evolution.mutation_rate, 0.2);
+        let config_path = directory.join("worlds").join("world-1.json");
+        assert!(config_path.exists());
+
+        let clone = registry.clone_world("world-1", Some("copy".to_string()), None).await.unwrap();
+        assert_eq!(clone.config.get().evolution.mutation_rate, 0.2);
+        assert_eq!(
+            clone.state.read().await.creatures.len(),
+            world.state.read().await.creatures.len()
+        );
+        assert_eq!(
+            registry.list().iter().map(|w| w.id.as_str()).collect::<Vec<_>>(),
+            vec!["copy", "world-1"]
+        );
+
+        registry.remove("world-1").await.unwrap();
+        assert!(!world.is_running());
+        assert!(!config_path.exists());
+        assert!(registry.get("world-1").is_none());
+        assert!(matches!(registry.remove("world-1").await, Err(WorldError::NotFound(_))));
+
+        registry.remove("copy").await.unwrap();
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_worlds() {
+        let (registry, directory) = registry("rejected");
+
+        assert!(matches!(
+            registry.create(Some("../up".to_string()), None),
+            Err(WorldError::InvalidId(_))
+        ));
+        assert!(matches!(
+            registry.create(None, Some(&serde_json::json!({ "server": { "port": 9000 } }))),
+            Err(WorldError::SharedFields(paths)) if paths == ["server.port"]
+        ));
+        assert!(matches!(registry.remove(DEFAULT_WORLD).await, Err(WorldError::DefaultWorld)));
+
+        registry.create(Some("a".to_string()), None).unwrap();
+        assert!(matches!(
+            registry.create(Some("a".to_string()), None),
+            Err(WorldError::AlreadyExists(_))
+        ));
+        // A clone shares the source's grid, so its size can't change
+        assert!(matches!(
+            registry
+                .clone_world("a", None, Some(&serde_json::json!({ "world": { "width": 60 } })))
+                .await,
+            Err(WorldError::Config(ConfigUpdateError::RestartRequired(_)))
+        ));
+
+        registry.remove("a").await.unwrap();
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,238 @@
+use super::control::{CommandReceiver, RunStateSender, SimulationCommand};
+use super::SimulationState;
+use crate::checkpoint;
+use crate::checkpoint::schedule::CheckpointSchedule;
+use crate::config::reload::{affects_simulation, ConfigUpdate, LiveConfig};
+use crate::config::Config;
+use crate::journal::{JournalHeader, JournalWriter};
+use crate::shutdown::{Shutdown, ShutdownReason};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::{interval, Duration, Instant, Interval};
+
+fn new_tick_interval(config: &Config) -> Interval {
+    interval(Duration::from_millis(1000 / config.simulation.ticks_per_second))
+}
+
+/// Pace `state` at the configured ticks per second, applying commands between
+/// ticks, until `shutdown` is triggered or every creature has died. Saves
+/// checkpoints and records the event journal as configured.
+pub async fn run_simulation(
+    state: Arc<RwLock<SimulationState>>,
+    live_config: LiveConfig,
+    run_state: RunStateSender,
+    shutdown: Shutdown,
+    mut commands: CommandReceiver,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = (*live_config.get()).clone();
+    let mut tick_interval = new_tick_interval(&config);
+
+    let mut checkpoint_schedule = if config.checkpoint.enabled {
+        Some(CheckpointSchedule::new(&config.checkpoint, &*state.read().await))
+    } else {
+        None
+    };
+
+    let mut journal = if config.checkpoint.journal_enabled {
+        Some(start_journal(&mut *state.write().await, &config)?)
+    } else {
+        None
+    };
+
+    let mut last_log = Instant::now();
+    let log_interval = Duration::from_secs(10);
+
+    loop {
+        tokio::select! {
+            _ = tick_interval.tick() => {
+                let mut advance = false;
+                run_state.send_if_modified(|run_state| {
+                    advance = run_state.take_tick();
+                    // Only a step taken while paused changes the run state
+                    advance && run_state.paused
+                });
+                if !advance {
+                    continue;
+                }
+            }
+            _ = shutdown.wait() => break,
+            Some(command) = commands.recv() => {
+                match command {
+                    SimulationCommand::Load { state: loaded, source, done } => {
+                        let mut loaded = *loaded;
+                        loaded.prepare_resume(&config);
+
+                        let mut sim_state = state.write().await;
+                        *sim_state = loaded;
+                        log::info!("Loaded checkpoint {} at tick {}", source.display(), sim_state.tick);
+
+                        // The old journal segment no longer leads to this state
+                        restart_journal(&mut journal, &mut sim_state, &config);
+                        if let Some(schedule) = checkpoint_schedule.as_mut() {
+                            schedule.mark_saved(&sim_state);
+                        }
+
+                        let _ = done.send(sim_state.tick);
+                    }
+                    SimulationCommand::UpdateConfig { config: new_config, source, done } => {
+                        let mut sim_state = state.write().await;
+                        let changes = match config.live_changes(&new_config) {
+                            Ok(changes) => changes,
+                            Err(e) => {
+                                let _ = done.send(Err(e));
+                                continue;
+                            }
+                        };
+
+                        for change in &changes {
+                            log::info!("Config change at tick {} from {}: {}", sim_state.tick, source, change);
+                        }
+                        if !changes.is_empty() {
+                            let tps_changed = new_config.simulation.ticks_per_second != config.simulation.ticks_per_second;
+                            config = *new_config;
+                            live_config.set(config.clone());
+
+                            if tps_changed {
+                                tick_interval = new_tick_interval(&config);
+                                run_state.send_modify(|run_state| {
+                                    run_state.ticks_per_second = config.simulation.ticks_per_second;
+                                });
+                            }
+                            if let Some(schedule) = checkpoint_schedule.as_mut() {
+                                schedule.reconfigure(&config.checkpoint);
+                            }
+                            // Replays run a segment with the config in its header, so
+                            // start a new segment from a checkpoint of this tick
+                            if journal.is_some() && changes.iter().any(|c| affects_simulation(&c.path)) {
+                                match checkpoint::save_checkpoint(&sim_state, &config) {
+                                    Ok(path) => log::info!("Checkpoint saved for the config change: {}", path),
+                                    Err(e) => log::error!("Failed to save checkpoint: {}", e),
+                                }
+                                restart_journal(&mut journal, &mut sim_state, &config);
+                            }
+                        }
+
+                        let _ = done.send(Ok(ConfigUpdate { tick: sim_state.tick, changes }));
+                    }
+                    SimulationCommand::Control { control, done } => {
+                        run_state.send_if_modified(|run_state| {
+                            let before = *run_state;
+                            run_state.apply(control);
+                            *run_state != before
+                        });
+                        let current = *run_state.borrow();
+                        log::info!(
+                            "Run control at tick {}: {:?} (paused: {}, pending steps: {})",
+                            state.read().await.tick,
+                            control,
+                            current.paused,
+                            current.pending_steps
+                        );
+                        let _ = done.send(current);
+                    }
+                }
+                continue;
+            }
+        }
+
+        {
+            let mut sim_state = state.write().await;
+            let tick = sim_state.tick;
+            sim_state.tick(&config);
+
+            if let Some(writer) = journal.as_mut() {
+                if let Err(e) = writer.append(tick, sim_state.events.take()) {
+                    log::error!("Failed to write event journal, disabling it: {}", e);
+                    sim_state.events.set_enabled(false);
+                    journal = None;
+                }
+            }
+
+            if last_log.elapsed() >= log_interval {
+                let metrics = sim_state.metrics();
+                log::info!(
+                    "Tick: {} | Population: {} | Avg Energy: {:.2} | Max Gen: {} | Food: {}",
+                    metrics.tick,
+                    metrics.population,
+                    metrics.avg_energy,
+                    metrics.max_generation,
+                    metrics.total_food
+                );
+                last_log = Instant::now();
+
+                if let Some(writer) = journal.as_mut() {
+                    if let Err(e) = writer.flush() {
+                        log::error!("Failed to flush event journal: {}", e);
+                    }
+                }
+
+                if metrics.population == 0 {
+                    log::warn!("All creatures have died! Simulation ended.");
+                    break;
+                }
+            }
+        }
+
+        if let Some(schedule) = checkpoint_schedule.as_mut() {
+            let sim_state = state.read().await;
+            if schedule.is_due(&sim_state) {
+                match checkpoint::save_checkpoint(&sim_state, &config) {
+                    Ok(path) => {
+                        log::info!("Checkpoint saved: {}", path);
+                    }
+                    Err(e) => {
+                        log::error!("Failed to save checkpoint: {}", e);
+                    }
+                }
+                schedule.mark_saved(&sim_state);
+            }
+        }
+    }
+
+    if let Some(writer) = journal.as_mut() {
+        writer.flush()?;
+    }
+
+    // Only a requested shutdown saves here; after an extinction the last
+    // periodic checkpoint is the one worth resuming, and a deleted world's
+    // checkpoints are about to be removed
+    let requested = shutdown.reason().is_some_and(|reason| reason != ShutdownReason::Deleted);
+    if checkpoint_schedule.is_some() && requested {
+        let sim_state = state.read().await;
+        let path = checkpoint::save_checkpoint(&sim_state, &config)?;
+        log::info!("Final checkpoint saved at tick {}: {}", sim_state.tick, path);
+    }
+
+    Ok(())
+}
+
+/// End the current journal segment, if any, and start a new one at the
+/// current state. Recording stops if the new segment can't be created.
+fn restart_journal(journal: &mut Option<JournalWriter>, sim_state: &mut SimulationState, config: &Config) {
+    let Some(writer) = journal.as_mut() else {
+        return;
+    };
+    if let Err(e) = writer.flush() {
+        log::error!("Failed to flush event journal: {}", e);
+    }
+    *journal = match start_journal(sim_state, config) {
+        Ok(writer) => Some(writer),
+        Err(e) => {
+            log::error!("Failed to start event journal, disabling it: {}", e);
+            sim_state.events.set_enabled(false);
+            None
+        }
+    };
+}
+
+/// Start a journal segment at the current state and turn on event recording
+fn start_journal(
+    sim_state: &mut SimulationState,
+    config: &Config,
+) -> Result<JournalWriter, Box<dyn std::error::Error>> {
+    let header = JournalHeader::new(sim_state.seed, sim_state.tick, config)?;
+    let writer = JournalWriter::create(&config.checkpoint.directory, &header)?;
+    log::info!("Recording event journal: {}", writer.path().display());
+    sim_state.events.set_enabled(true);
+    Ok(writer)
+}
@@ -0,0 +1,402 @@
+use super::batch::{run_batch, BatchOptions, StopReason};
+use super::SimulationState;
+use crate::config::overrides::ConfigOverride;
+use crate::config::Config;
+use crate::stats::diversity::genetic_diversity;
+use crate::stats::export::MetricsFormat;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// Most values a single `start:end:step` range may expand to
+const MAX_RANGE_VALUES: usize = 10_000;
+
+/// A config field and the values a sweep gives it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepAxis {
+    pub path: String,
+    /// Values as they would be given to `--set`
+    pub values: Vec<String>,
+}
+
+impl SweepAxis {
+    /// Parse `path=start:end:step`, with `end` included, or `path=a,b,c`
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        let assignment = ConfigOverride::from_assignment(arg)?;
+        let spec = assignment.raw.trim();
+
+        let values = match spec.split(':').collect::<Vec<_>>()[..] {
+            [start, end, step] => {
+                let number = |s: &str| {
+                    s.trim()
+                        .parse::<f64>()
+                        .map_err(|_| format!("{}: {:?} is not a number", assignment.path, s))
+                };
+                range_values(number(start)?, number(end)?, number(step)?)
+                    .map_err(|e| format!("{}: {}", assignment.path, e))?
+            }
+            [_] => spec.split(',').map(|v| v.trim().to_string()).collect(),
+            _ => {
+                return Err(format!(
+                    "{}: expected start:end:step or a comma-separated list, got {:?}",
+                    assignment.path, spec
+                ))
+            }
+        };
+        if values.iter().any(String::is_empty) {
+            return Err(format!("{}: empty value in {:?}", assignment.path, spec));
+        }
+
+        Ok(Self {
+            path: assignment.path,
+            values,
+        })
+    }
+}
+
+/// `start`, `start + step`, ... up to and including `end`. Whole-number
+/// ranges are written without a decimal point so integer fields accept them.
+fn range_values(start: f64, end: f64, step: f64) -> Result<Vec<String>, String> {
+    if step <= 0.0 || !step.is_finite() {
+        return Err(format!("step must be positive, got {}", step));
+    }
+    if end < start {
+        return Err(format!("end {} is below start {}", end, start));
+    }
+    // Tolerate rounding so that 0.1:0.3:0.1 includes 0.3
+    let count = ((end - start) / step + 1e-9).floor() as usize + 1;
+    if count > MAX_RANGE_VALUES {
+        return Err(format!("range has {} values, more than {}", count, MAX_RANGE_VALUES));
+    }
+
+    let integers = start.fract() == 0.0 && step.fract() == 0.0;
+    Ok((0..count)
+        .map(|i| {
+            let value = start + i as f64 * step;
+            if integers {
+                format!("{}", value as i64)
+            } else {
+                format!("{}", (value * 1e12).round() / 1e12)
+            }
+        })
+        .collect())
+}
+
+/// One combination of swept values with one seed
+#[derive(Debug, Clone)]
+pub struct SweepRun {
+    pub index: usize,
+    /// Swept field values, in axis order
+    pub parameters: Vec<(String, String)>,
+    pub seed: u64,
+    pub config: Config,
+}
+
+/// Build a run for every combination of axis values and seeds, ordered by
+/// the first axis, then the next, and seed last. Every config is validated
+/// up front so a bad value doesn't surface halfway through a sweep.
+pub fn plan(base: &Config, axes: &[SweepAxis], seeds: &[u64]) -> Result<Vec<SweepRun>, String> {
+    for (i, axis) in axes.iter().enumerate() {
+        if axes[..i].iter().any(|other| other.path == axis.path) {
+            return Err(format!("{} is swept more than once", axis.path));
+        }
+    }
+    if seeds.is_empty() {
+        return Err("A sweep needs at least one seed".to_string());
+    }
+
+    let mut combinations: Vec<Vec<(String, String)>> = vec![Vec::new()];
+    for axis in axes {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                axis.values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.push((axis.path.clone(), value.clone()));
+                    combination
+                })
+            })
+            .collect();
+    }
+
+    let mut runs = Vec::with_capacity(combinations.len() * seeds.len());
+    for parameters in combinations {
+        let overrides: Vec<ConfigOverride> = parameters
+            .iter()
+            .map(|(path, raw)| ConfigOverride {
+                source: "sweep".to_string(),
+                path: path.clone(),
+                raw: raw.clone(),
+            })
+            .collect();
+        let mut config = base.with_overrides(&overrides)?;
+        // Runs are compared by their summaries; nothing is written per run
+        config.checkpoint.enabled = false;
+        config.checkpoint.journal_enabled = false;
+        if let Err(errors) = config.validate() {
+            return Err(format!("{}: {}", describe(&parameters), errors));
+        }
+
+        for &seed in seeds {
+            let mut config = config.clone();
+            config.simulation.seed = Some(seed);
+            runs.push(SweepRun {
+                index: runs.len(),
+                parameters: parameters.clone(),
+                seed,
+                config,
+            });
+        }
+    }
+    Ok(runs)
+}
+
+fn describe(parameters: &[(String, String)]) -> String {
+    parameters
+        .iter()
+        .map(|(path, value)| format!("{}={}", path, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Outcome of one sweep run
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepResult {
+    pub run: usize,
+    /// Swept field values by path, as JSON where they parse as JSON
+    pub parameters: BTreeMap<String, Value>,
+    pub seed: u64,
+    pub reason: StopReason,
+    pub ticks: u64,
+    pub final_population: usize,
+    pub max_generation: u64,
+    /// Tick at which the last creature died, if the population died out
+    pub extinction_tick: Option<u64>,
+    /// `genetic_diversity` of the final population
+    pub diversity: f64,
+    pub elapsed_seconds: f64,
+}
+
+/// Run every planned run to completion on the current rayon pool.
+/// `on_finish` is called as each run completes, in whatever order they finish.
+pub fn run_sweep(
+    runs: Vec<SweepRun>,
+    options: &BatchOptions,
+    should_stop: impl Fn() -> bool + Sync,
+    on_finish: impl Fn(&SweepResult) + Sync,
+) -> Result<SweepSummary, String> {
+    let results = runs
+        .into_par_iter()
+        .map(|run| {
+            let started = Instant::now();
+            let mut state = SimulationState::new(&run.config);
+            let summary = run_batch(&mut state, &run.config, options, None, &should_stop)
+                .map_err(|e| format!("Run {} ({}): {}", run.index, describe(&run.parameters), e))?;
+
+            let result = SweepResult {
+                run: run.index,
+                parameters: run
+                    .parameters
+                    .into_iter()
+                    .map(|(path, raw)| {
+                        let value = serde_json::from_str(&raw).unwrap_or(Value::String(raw));
+                        (path, value)
+                    })
+                    .collect(),
+                seed: run.seed,
+                reason: summary.reason,
+                ticks: summary.final_metrics.tick,
+                final_population: summary.final_metrics.population,
+                max_generation: summary.final_metrics.max_generation,
+                extinction_tick: (summary.reason == StopReason::Extinction).then_some(state.tick),
+                diversity: genetic_diversity(state.creatures.values()),
+                elapsed_seconds: started.elapsed().as_secs_f64(),
+            };
+            on_finish(&result);
+            Ok(result)
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(SweepSummary { results })
+}
+
+/// Results of every run, in plan order
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepSummary {
+    pub results: Vec<SweepResult>,
+}
+
+impl SweepSummary {
+    fn parameter_columns(&self) -> Vec<&str> {
+        self.results
+            .first()
+            .map(|r| r.parameters.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// Write one row per run as CSV or NDJSON
+    pub fn write(&self, path: &Path, format: MetricsFormat) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        match format {
+            MetricsFormat::Ndjson => {
+                for result in &self.results {
+                    serde_json::to_writer(&mut writer, result)?;
+                    writeln!(writer)?;
+                }
+            }
+            MetricsFormat::Csv => {
+                let columns = self.parameter_columns();
+                writeln!(
+                    writer,
+                    "run,{}seed,reason,ticks,final_population,max_generation,extinction_tick,diversity",
+                    columns.iter().map(|c| format!("{},", c)).collect::<String>()
+                )?;
+                for r in &self.results {
+                    write!(writer, "{},", r.run)?;
+                    for column in &columns {
+                        match r.parameters.get(*column) {
+                            Some(Value::String(s)) => write!(writer, "{},", s)?,
+                            Some(value) => write!(writer, "{},", value)?,
+                            None => write!(writer, ",")?,
+                        }
+                    }
+                    let reason = serde_json::to_value(r.reason)?;
+                    writeln!(
+                        writer,
+                        "{},{},{},{},{},{},{}",
+                        r.seed,
+                        reason.as_str().unwrap_or_default(),
+                        r.ticks,
+                        r.final_population,
+                        r.max_generation,
+                        r.extinction_tick.map(|t| t.to_string()).unwrap_or_default(),
+                        r.diversity
+                    )?;
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for SweepSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns = self.parameter_columns();
+        let value = |v: Option<&Value>| match v {
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => String::new(),
+        };
+        let widths: Vec<usize> = columns
+            .iter()
+            .map(|c| {
+                self.results
+                    .iter()
+                    .map(|r| value(r.parameters.get(*c)).len())
+                    .chain([c.len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        write!(f, "{:>4}", "run")?;
+        for (column, width) in columns.iter().zip(&widths) {
+            write!(f, "  {:>width$}", column, width = width)?;
+        }
+        writeln!(
+            f,
+            "  {:>6} {:>8} {:>10} {:>7} {:>10} {:>9}",
+            "seed", "ticks", "population", "max gen", "extinction", "diversity"
+        )?;
+
+        for r in &self.results {
+            write!(f, "{:>4}", r.run)?;
+            for (column, width) in columns.iter().zip(&widths) {
+                write!(f, "  {:>width$}", value(r.parameters.get(*column)), width = width)?;
+            }
+            writeln!(
+                f,
+                "  {:>6} {:>8} {:>10} {:>7} {:>10} {:>9.3}",
+                r.seed,
+                r.ticks,
+                r.final_population,
+                r.max_generation,
+                r.extinction_tick.map(|t| t.to_string()).unwrap_or_else(|| "-".to_string()),
+                r.diversity
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_axes() {
+        let axis = SweepAxis::parse("creature.energy_per_food=10:50:20").unwrap();
+        assert_eq!(axis.path, "creature.energy_per_food");
+        assert_eq!(axis.values, vec!["10", "30", "50"]);
+
+        let axis = SweepAxis::parse("creature.energy_cost_per_tick=0.1:0.3:0.1").unwrap();
+        assert_eq!(axis.values, vec!["0.1", "0.2", "0.3"]);
+
+        let axis = SweepAxis::parse("evolution.mutation_rate=0.01, 0.05").unwrap();
+        assert_eq!(axis.values, vec!["0.01", "0.05"]);
+
+        assert!(SweepAxis::parse("world.width").is_err());
+        assert!(SweepAxis::parse("world.width=10:5:1").is_err());
+        assert!(SweepAxis::parse("world.width=1:5:0").is_err());
+        assert!(SweepAxis::parse("world.width=1:2").is_err());
+    }
+
+    #[test]
+    fn test_plan_and_run() {
+        let mut base = Config::default();
+        base.world.width = 30;
+        base.world.height = 30;
+        base.creature.initial_population = 10;
+
+        let axes = [
+            SweepAxis::parse("creature.energy_per_food=20,40").unwrap(),
+            SweepAxis::parse("evolution.mutation_rate=0.01:0.02:0.01").unwrap(),
+        ];
+        let runs = plan(&base, &axes, &[1, 2]).unwrap();
+        assert_eq!(runs.len(), 8);
+        assert_eq!(runs[3].config.creature.energy_per_food, 20.0);
+        assert_eq!(runs[3].config.evolution.mutation_rate, 0.02);
+        assert_eq!(runs[3].seed, 2);
+        assert!(!runs[3].config.checkpoint.enabled);
+
+        let invalid = [SweepAxis::parse("evolution.mutation_rate=0.5,2").unwrap()];
+        assert!(plan(&base, &invalid, &[1]).unwrap_err().contains("evolution.mutation_rate=2"));
+
+        let options = BatchOptions {
+            max_ticks: Some(20),
+            ..Default::default()
+        };
+        let summary = run_sweep(runs[..2].to_vec(), &options, || false, |_| {}).unwrap();
+        assert_eq!(summary.results.len(), 2);
+        assert_eq!(summary.results[1].run, 1);
+        assert_eq!(summary.results[1].seed, 2);
+        assert_eq!(summary.results[0].parameters["creature.energy_per_food"], serde_json::json!(20));
+        assert!(summary.to_string().contains("diversity"));
+
+        // Runs are seeded, so a sweep is reproducible
+        let again = run_sweep(runs[..1].to_vec(), &options, || false, |_| {}).unwrap();
+        assert_eq!(again.results[0].final_population, summary.results[0].final_population);
+        assert_eq!(again.results[0].diversity, summary.results[0].diversity);
+    }
+}
@@ -1,12 +1,19 @@
+use super::events::SimEvent;
+use super::rng;
 use super::SimulationState;
-use crate::config::Config;
+use crate::config::{Config, PlantMode};
 use crate::creature::{neural_net::Action, Creature};
+use crate::world::resources::FoodDrop;
+use crate::world::{Terrain, Topology};
 use rand::seq::SliceRandom;
-use rand::Rng;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Number of inputs filled in by `get_sensor_inputs`
+pub const SENSOR_COUNT: usize = 42;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -15,23 +22,64 @@ pub enum Direction {
 }
 
 impl SimulationState {
+    /// Advance the simulation by one tick, with any config schedule
+    /// evaluated at the current tick
     pub fn tick(&mut self, config: &Config) {
-        // Food regeneration
-        self.world
-            .regenerate_food(config.world.food_regen_rate, config.world.max_food_per_cell);
+        match config.scheduled_at(self.tick) {
+            Some((scheduled, values)) => {
+                self.run_tick(&scheduled);
+                self.scheduled_values = values;
+            }
+            None => {
+                self.run_tick(config);
+                self.scheduled_values.clear();
+            }
+        }
+    }
+
+    fn run_tick(&mut self, config: &Config) {
+        let climate = self.climate(config);
+
+        // Food regeneration, faster in summer and slower in winter
+        let mut fertility = config.world.fertility();
+        for biome in &mut fertility {
+            biome.food_regen_rate *= climate.food_regen_multiplier();
+        }
+        let spawned_food = self.world.regenerate_food(&fertility, &mut self.rng);
+        if self.events.is_enabled() {
+            for drop in spawned_food {
+                self.record_food_drop(drop, false);
+            }
+        }
+
+        // Plant growth and seeding. Plants that grow in place don't age out.
+        if config.world.plants.mode == PlantMode::Ecology {
+            let plants = &config.world.plants;
+            let growth_rate = plants.growth_rate * climate.food_regen_multiplier();
+            let grown = self
+                .world
+                .grow_plants(growth_rate, plants.seed_rate, &fertility, &mut self.rng);
+            if self.events.is_enabled() {
+                for drop in grown {
+                    self.record_food_drop(drop, false);
+                }
+            }
+            for biome in &mut fertility {
+                biome.plant_decay_ticks = u32::MAX;
+            }
+        }
 
         // Food aging and decay
-        self.world.age_and_decay_food(
-            config.world.plant_decay_ticks,
-            config.world.meat_decay_ticks,
-        );
+        self.world.age_and_decay_food(&fertility, config.world.meat_decay_ticks);
+        let energy_cost_per_tick = config.creature.energy_cost_per_tick * climate.metabolism_multiplier();
 
         // OPTIMIZATION: Pre-allocate with capacity to avoid reallocation
         let num_creatures = self.creatures.len();
         let mut creature_ids: Vec<u64> = Vec::with_capacity(num_creatures);
         creature_ids.extend(self.creatures.keys().copied());
-        let mut rng = rand::thread_rng();
-        creature_ids.shuffle(&mut rng);
+        // Sort first: HashMap iteration order differs between processes
+        creature_ids.sort_unstable();
+        creature_ids.shuffle(&mut self.rng);
 
         // PHASE 1 (Sequential): Pre-process all creatures - aging, energy consumption, healing
         // This must be sequential to maintain deterministic state updates
@@ -48,7 +96,7 @@ impl SimulationState {
                     creature.metabolism.take_damage(creature.metabolism.health());
                 }
 
-                creature.consume_energy(config.creature.energy_cost_per_tick);
+                creature.consume_energy(energy_cost_per_tick);
 
                 // Check for death from zero energy (starvation)
                 if creature.energy() <= 0.0 {
@@ -67,6 +115,9 @@ impl SimulationState {
         // OPTIMIZATION: This is the main bottleneck - each creature does expensive spatial queries
         // and neural network forward pass. Since this is read-only, we can parallelize it.
         // Expected speedup: 4-8× on multi-core CPUs
+        // Each creature samples from its own RNG stream so results don't depend on scheduling
+        let seed = self.seed;
+        let current_tick = self.tick;
         let creature_actions: Vec<(u64, usize, usize, Action)> = creature_ids
             .par_iter()
             .filter_map(|&id| {
@@ -85,7 +136,8 @@ impl SimulationState {
                 let inputs = self.get_sensor_inputs(id, x, y, energy, config);
 
                 // Neural network forward pass (expensive: 336 multiplications + 20 tanh)
-                let action = creature.decide_action(&inputs);
+                let mut creature_rng = rng::creature_rng(seed, current_tick, id);
+                let action = creature.decide_action(&inputs, &mut creature_rng);
 
                 Some((id, x, y, action))
             })
@@ -162,13 +214,16 @@ impl SimulationState {
                                 config.creature.energy_cost_reproduce,
                                 config.creature.initial_energy,
                                 config.creature.max_energy,
-                                (
-                                    config.evolution.neural_net_inputs,
-                                    config.evolution.neural_net_hidden,
-                                    config.evolution.neural_net_outputs,
-                                ),
                                 self.tick,
+                                &mut self.rng,
                             ) {
+                                self.events.record(SimEvent::Birth {
+                                    id: offspring.id,
+                                    parent_id: id,
+                                    x: offspring.x,
+                                    y: offspring.y,
+                                    generation: offspring.genome.generation,
+                                });
                                 new_creatures.push(offspring);
                                 self.next_creature_id += 1;
                                 self.total_births += 1;
@@ -186,20 +241,24 @@ impl SimulationState {
         }
 
         // Handle deaths: spawn meat food and update spatial index
-        let dead_creatures: Vec<(u64, usize, usize, f64)> = self
+        let mut dead_creatures: Vec<(u64, usize, usize, f64, u64)> = self
             .creatures
             .iter()
             .filter(|(_, c)| !c.is_alive())
-            .map(|(id, c)| (*id, c.x, c.y, c.energy()))
+            .map(|(id, c)| (*id, c.x, c.y, c.energy(), c.age))
             .collect();
+        dead_creatures.sort_unstable_by_key(|&(id, ..)| id);
+
+        for &(dead_id, x, y, remaining_energy, age) in &dead_creatures {
+            self.events.record(SimEvent::Death { id: dead_id, x, y, age });
 
-        for (_dead_id, x, y, remaining_energy) in dead_creatures {
             // Spawn meat food based on remaining energy
             let meat_amount = (remaining_energy / 20.0).ceil() as u32;
             if meat_amount > 0 {
                 if let Some(cell) = self.world.get_mut(x, y) {
-                    cell.add_food(meat_amount, config.world.max_food_per_cell, true);
                     // true = meat
+                    let (amount, replaced) = cell.add_food(meat_amount, config.world.max_food_per_cell, true);
+                    self.record_food_drop(FoodDrop { x, y, amount, replaced }, true);
                 }
             }
 
@@ -208,8 +267,8 @@ impl SimulationState {
         }
 
         // Save dying creatures to buffer before removal (for extinction failsafe)
-        for creature in self.creatures.values() {
-            if !creature.is_alive() {
+        for &(dead_id, ..) in &dead_creatures {
+            if let Some(creature) = self.creatures.get(&dead_id) {
                 self.recently_dead.push_back(creature.clone());
                 // Keep buffer size reasonable (last 100 dead creatures)
                 if self.recently_dead.len() > 100 {
@@ -219,8 +278,7 @@ impl SimulationState {
         }
 
         // Count and remove dead creatures
-        let deaths_this_tick = self.creatures.values().filter(|c| !c.is_alive()).count() as u64;
-        self.total_deaths += deaths_this_tick;
+        self.total_deaths += dead_creatures.len() as u64;
         self.creatures.retain(|_, c| c.is_alive());
 
         // Extinction failsafe: resurrect recently dead creatures if population reaches 0
@@ -231,7 +289,6 @@ impl SimulationState {
                 config.creature.initial_population
             );
 
-            let mut rng = rand::thread_rng();
             let num_to_resurrect = config
                 .creature
                 .initial_population
@@ -247,16 +304,14 @@ impl SimulationState {
                     self.next_creature_id += 1;
 
                     // Find a random empty position
-                    let mut new_x = rng.gen_range(0..self.world.width());
-                    let mut new_y = rng.gen_range(0..self.world.height());
+                    let (mut new_x, mut new_y) = self.world.random_open_cell(&mut self.rng);
 
                     // Try a few times to find unoccupied space
                     for _ in 0..10 {
                         if self.creature_at(new_x, new_y).is_none() {
                             break;
                         }
-                        new_x = rng.gen_range(0..self.world.width());
-                        new_y = rng.gen_range(0..self.world.height());
+                        (new_x, new_y) = self.world.random_open_cell(&mut self.rng);
                     }
 
                     // Create resurrected creature with full health and energy
@@ -267,13 +322,15 @@ impl SimulationState {
                         dead_creature.genome.clone(),
                         config.creature.initial_energy,
                         config.creature.max_energy,
-                        (
-                            config.evolution.neural_net_inputs,
-                            config.evolution.neural_net_hidden,
-                            config.evolution.neural_net_outputs,
-                        ),
+                        dead_creature.brain.dimensions(),
                     );
 
+                    self.events.record(SimEvent::Resurrection {
+                        id: new_id,
+                        source_id: dead_creature.id,
+                        x: new_x,
+                        y: new_y,
+                    });
                     self.add_creature_to_position(new_id, new_x, new_y);
                     self.creatures.insert(new_id, resurrected);
                 }
@@ -299,11 +356,22 @@ impl SimulationState {
         energy: f64,
         config: &Config,
     ) -> Vec<f64> {
-        let mut inputs = vec![0.0; config.evolution.neural_net_inputs];
+        // Sized for the creature's own brain, which has fewer inputs than the
+        // config asks for when it was resumed from an older checkpoint
+        let input_count = self
+            .creatures
+            .get(&creature_id)
+            .map_or(config.evolution.neural_net_inputs, |c| c.brain.dimensions().0);
+        let mut inputs = vec![0.0; input_count];
 
         // Input 0: Energy ratio
         inputs[0] = energy / config.creature.max_energy;
 
+        // Creatures see less far at night. Adjacent cells are always sensed.
+        let climate = self.climate(config);
+        let range = climate.sensor_range();
+        let scaled = |radius: usize| ((radius as f64 * range).round() as usize).max(1);
+
         let neighbors = self.world.neighbors(x, y);
         let mut food_count = 0;
         let mut empty_count = 0;
@@ -326,72 +394,70 @@ impl SimulationState {
         }
 
         // Input 1: Nearby food count (0.0-1.0)
-        if config.evolution.neural_net_inputs > 1 {
+        if input_count > 1 {
             inputs[1] = food_count as f64 / 8.0;
         }
 
         // Input 2: Empty neighbor count (0.0-1.0)
-        if config.evolution.neural_net_inputs > 2 {
+        if input_count > 2 {
             inputs[2] = empty_count as f64 / 8.0;
         }
 
         // Input 3: Food at current position (0.0 or 1.0)
         if let Some(cell) = self.world.get(x, y) {
-            if config.evolution.neural_net_inputs > 3 && cell.is_food() {
+            if input_count > 3 && cell.is_food() {
                 inputs[3] = 1.0;
             }
         }
 
         // Input 4: Nearby creature density (0.0-1.0)
-        let nearby_creatures = self.count_nearby_creatures(x, y, 5);
-        if config.evolution.neural_net_inputs > 4 {
+        let nearby_creatures = self.count_nearby_creatures(x, y, scaled(5));
+        if input_count > 4 {
             inputs[4] = (nearby_creatures as f64 / 25.0).min(1.0);
         }
 
         // Inputs 5-8: Creature detected in [Up, Down, Left, Right]
-        if config.evolution.neural_net_inputs > 5 {
-            if let Some(_) = self.creature_at(x, y.wrapping_sub(1)) {
-                inputs[5] = 1.0; // Up
-            }
+        let creature_towards = |dx: i64, dy: i64| {
+            self.world
+                .offset(x, y, dx, dy)
+                .and_then(|(nx, ny)| self.creature_at(nx, ny))
+                .is_some()
+        };
+        if input_count > 5 && creature_towards(0, -1) {
+            inputs[5] = 1.0; // Up
         }
-        if config.evolution.neural_net_inputs > 6 {
-            if let Some(_) = self.creature_at(x, y + 1) {
-                inputs[6] = 1.0; // Down
-            }
+        if input_count > 6 && creature_towards(0, 1) {
+            inputs[6] = 1.0; // Down
         }
-        if config.evolution.neural_net_inputs > 7 {
-            if let Some(_) = self.creature_at(x.wrapping_sub(1), y) {
-                inputs[7] = 1.0; // Left
-            }
+        if input_count > 7 && creature_towards(-1, 0) {
+            inputs[7] = 1.0; // Left
         }
-        if config.evolution.neural_net_inputs > 8 {
-            if let Some(_) = self.creature_at(x + 1, y) {
-                inputs[8] = 1.0; // Right
-            }
+        if input_count > 8 && creature_towards(1, 0) {
+            inputs[8] = 1.0; // Right
         }
 
         // Inputs 9-12: Being attacked from [Up, Down, Left, Right]
         if let Some(attack_dirs) = self.attacks_last_tick.get(&creature_id) {
             for &dir in attack_dirs {
                 match dir {
-                    Direction::Up if config.evolution.neural_net_inputs > 9 => inputs[9] = 1.0,
-                    Direction::Down if config.evolution.neural_net_inputs > 10 => inputs[10] = 1.0,
-                    Direction::Left if config.evolution.neural_net_inputs > 11 => inputs[11] = 1.0,
-                    Direction::Right if config.evolution.neural_net_inputs > 12 => inputs[12] = 1.0,
+                    Direction::Up if input_count > 9 => inputs[9] = 1.0,
+                    Direction::Down if input_count > 10 => inputs[10] = 1.0,
+                    Direction::Left if input_count > 11 => inputs[11] = 1.0,
+                    Direction::Right if input_count > 12 => inputs[12] = 1.0,
                     _ => {}
                 }
             }
         }
 
         // Input 13: Own health ratio
-        if config.evolution.neural_net_inputs > 13 {
+        if input_count > 13 {
             if let Some(creature) = self.creatures.get(&creature_id) {
                 inputs[13] = creature.metabolism.health_ratio();
             }
         }
 
         // Input 14: Nearby plant food ratio (0.0-1.0)
-        if config.evolution.neural_net_inputs > 14 {
+        if input_count > 14 {
             inputs[14] = if food_count > 0 {
                 plant_food_count as f64 / food_count as f64
             } else {
@@ -400,7 +466,7 @@ impl SimulationState {
         }
 
         // Input 15: Nearby meat food ratio (0.0-1.0)
-        if config.evolution.neural_net_inputs > 15 {
+        if input_count > 15 {
             inputs[15] = if food_count > 0 {
                 meat_food_count as f64 / food_count as f64
             } else {
@@ -409,14 +475,14 @@ impl SimulationState {
         }
 
         // Input 16: Age ratio (age / max_age)
-        if config.evolution.neural_net_inputs > 16 {
+        if input_count > 16 {
             if let Some(creature) = self.creatures.get(&creature_id) {
                 inputs[16] = (creature.age as f64 / config.creature.max_age_ticks as f64).min(1.0);
             }
         }
 
         // Input 17: Can reproduce (boolean)
-        if config.evolution.neural_net_inputs > 17 {
+        if input_count > 17 {
             if let Some(creature) = self.creatures.get(&creature_id) {
                 inputs[17] = if creature.can_reproduce(
                     config.creature.min_reproduce_energy,
@@ -431,55 +497,55 @@ impl SimulationState {
         }
 
         // Input 18: Offspring count (normalized to 0-1, capped at 10)
-        if config.evolution.neural_net_inputs > 18 {
+        if input_count > 18 {
             if let Some(creature) = self.creatures.get(&creature_id) {
                 inputs[18] = (creature.offspring_count as f64 / 10.0).min(1.0);
             }
         }
 
         // Input 19: Recent damage taken (normalized)
-        if config.evolution.neural_net_inputs > 19 {
+        if input_count > 19 {
             if let Some(creature) = self.creatures.get(&creature_id) {
                 inputs[19] = (creature.last_damage_taken / 50.0).min(1.0);
             }
         }
 
-        // Input 20: Distance to top boundary (normalized)
-        if config.evolution.neural_net_inputs > 20 {
-            inputs[20] = (y as f64 / config.world.height as f64).min(1.0);
-        }
-
-        // Input 21: Distance to bottom boundary (normalized)
-        if config.evolution.neural_net_inputs > 21 {
-            inputs[21] = ((config.world.height - y) as f64 / config.world.height as f64).min(1.0);
-        }
-
-        // Input 22: Distance to left boundary (normalized)
-        if config.evolution.neural_net_inputs > 22 {
-            inputs[22] = (x as f64 / config.world.width as f64).min(1.0);
-        }
-
-        // Input 23: Distance to right boundary (normalized)
-        if config.evolution.neural_net_inputs > 23 {
-            inputs[23] = ((config.world.width - x) as f64 / config.world.width as f64).min(1.0);
+        // Inputs 20-23: Distance to [top, bottom, left, right] boundary
+        // (normalized). A torus has no boundary, so they read as far away.
+        let (width, height) = (self.world.width(), self.world.height());
+        let boundary_distances = match self.world.topology() {
+            Topology::Bounded => [
+                y as f64 / height as f64,
+                (height - y) as f64 / height as f64,
+                x as f64 / width as f64,
+                (width - x) as f64 / width as f64,
+            ],
+            Topology::Torus => [1.0; 4],
+        };
+        for (i, distance) in boundary_distances.into_iter().enumerate() {
+            if input_count > 20 + i {
+                inputs[20 + i] = distance.min(1.0);
+            }
         }
 
         // Inputs 24-26: Nearest creature distance, energy, and health
-        if config.evolution.neural_net_inputs > 24 {
-            let nearest = self.find_nearest_creature(creature_id, x, y);
+        if input_count > 24 {
+            let nearest = self
+                .find_nearest_creature(creature_id, x, y)
+                .filter(|&(dist, _)| range >= 1.0 || dist <= 20.0 * range);
             if let Some((dist, nearest_id)) = nearest {
                 // Input 24: Distance to nearest creature (normalized to max 20 cells)
                 inputs[24] = (dist / 20.0).min(1.0);
 
                 // Input 25: Nearest creature energy ratio
-                if config.evolution.neural_net_inputs > 25 {
+                if input_count > 25 {
                     if let Some(nearest_creature) = self.creatures.get(&nearest_id) {
                         inputs[25] = nearest_creature.metabolism.energy_ratio();
                     }
                 }
 
                 // Input 26: Nearest creature health ratio
-                if config.evolution.neural_net_inputs > 26 {
+                if input_count > 26 {
                     if let Some(nearest_creature) = self.creatures.get(&nearest_id) {
                         inputs[26] = nearest_creature.metabolism.health_ratio();
                     }
@@ -488,59 +554,93 @@ impl SimulationState {
         }
 
         // Input 27: Nearby kin density (creatures with similar generation)
-        if config.evolution.neural_net_inputs > 27 {
+        if input_count > 27 {
             if let Some(creature) = self.creatures.get(&creature_id) {
                 let kin_count =
-                    self.count_nearby_kin(creature_id, x, y, creature.genome.generation, 5);
+                    self.count_nearby_kin(creature_id, x, y, creature.genome.generation, scaled(5));
                 inputs[27] = (kin_count as f64 / 25.0).min(1.0);
             }
         }
 
         // Input 28: Food density in 5×5 area
-        if config.evolution.neural_net_inputs > 28 {
-            let food_density = self.count_food_in_area(x, y, 2);
+        if input_count > 28 {
+            let food_density = self.count_food_in_area(x, y, scaled(2));
             inputs[28] = (food_density as f64 / 25.0).min(1.0);
         }
 
         // Input 29: Creature density in 3×3 area
-        if config.evolution.neural_net_inputs > 29 {
+        if input_count > 29 {
             let local_creatures = self.count_nearby_creatures(x, y, 1);
             inputs[29] = (local_creatures as f64 / 9.0).min(1.0);
         }
 
         // Inputs 30-33: Food detected in [Up, Down, Left, Right]
-        if config.evolution.neural_net_inputs > 30 {
-            if let Some(cell) = self.world.get(x, y.wrapping_sub(1)) {
-                if cell.is_food() {
-                    inputs[30] = 1.0; // Up
-                }
-            }
+        let food_towards = |dx: i64, dy: i64| {
+            self.world
+                .offset(x, y, dx, dy)
+                .and_then(|(nx, ny)| self.world.get(nx, ny))
+                .is_some_and(|cell| cell.is_food())
+        };
+        if input_count > 30 && food_towards(0, -1) {
+            inputs[30] = 1.0; // Up
         }
-        if config.evolution.neural_net_inputs > 31 {
-            if let Some(cell) = self.world.get(x, y + 1) {
-                if cell.is_food() {
-                    inputs[31] = 1.0; // Down
-                }
-            }
+        if input_count > 31 && food_towards(0, 1) {
+            inputs[31] = 1.0; // Down
         }
-        if config.evolution.neural_net_inputs > 32 {
-            if let Some(cell) = self.world.get(x.wrapping_sub(1), y) {
-                if cell.is_food() {
-                    inputs[32] = 1.0; // Left
-                }
+        if input_count > 32 && food_towards(-1, 0) {
+            inputs[32] = 1.0; // Left
+        }
+        if input_count > 33 && food_towards(1, 0) {
+            inputs[33] = 1.0; // Right
+        }
+
+        // Inputs 34-37: Terrain in [Up, Down, Left, Right], from 0 for plain
+        // ground to 1 for a wall. The edge of a bounded world reads as a wall.
+        let directions = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+        for (i, (dx, dy)) in directions.into_iter().enumerate() {
+            if input_count > 34 + i {
+                inputs[34 + i] = self
+                    .world
+                    .offset(x, y, dx, dy)
+                    .map_or(1.0, |(nx, ny)| self.world.terrain(nx, ny).sensor_value());
             }
         }
-        if config.evolution.neural_net_inputs > 33 {
-            if let Some(cell) = self.world.get(x + 1, y) {
-                if cell.is_food() {
-                    inputs[33] = 1.0; // Right
-                }
+
+        // Inputs 38-41: Time of year and of day, as how far through the
+        // cycle and how warm or light it is. A disabled cycle reads as
+        // phase 0, warmth 0.5 and full daylight.
+        let clock = [
+            climate.season_phase(),
+            climate.warmth(),
+            climate.day_phase(),
+            climate.daylight(),
+        ];
+        for (i, value) in clock.into_iter().enumerate() {
+            if input_count > 38 + i {
+                inputs[38 + i] = value;
             }
         }
 
         inputs
     }
 
+    /// Journal food added to a cell, and any food of the other kind it
+    /// replaced
+    fn record_food_drop(&mut self, drop: FoodDrop, is_meat: bool) {
+        let FoodDrop { x, y, amount, replaced } = drop;
+        if replaced > 0 {
+            self.events.record(SimEvent::FoodReplaced {
+                x,
+                y,
+                amount: replaced,
+                is_meat: !is_meat,
+            });
+        }
+        if amount > 0 {
+            self.events.record(SimEvent::FoodSpawn { x, y, amount, is_meat });
+        }
+    }
+
     fn try_eat(&mut self, creature_id: u64, config: &Config) {
         if let Some(creature) = self.creatures.get(&creature_id) {
             let x = creature.x;
@@ -548,28 +648,33 @@ impl SimulationState {
 
             if let Some(cell) = self.world.get_mut(x, y) {
                 if cell.is_food() {
-                    let (food_amount, _is_meat) = cell.consume_food();
-                    // For now, treat plant and meat food the same
-                    let energy_gain = food_amount as f64 * config.creature.energy_per_food;
+                    let (food_amount, is_meat) = cell.consume_food();
+                    let energy_per_unit = if is_meat {
+                        config.creature.energy_per_meat
+                    } else {
+                        config.creature.energy_per_food
+                    };
 
                     if let Some(creature) = self.creatures.get_mut(&creature_id) {
-                        creature.gain_energy(energy_gain);
+                        let efficiency =
+                            creature.digestive_efficiency(is_meat, config.creature.off_diet_efficiency);
+                        creature.gain_energy(food_amount as f64 * energy_per_unit * efficiency);
                     }
                 }
             }
         }
     }
 
-    fn find_empty_neighbor(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+    fn find_empty_neighbor(&mut self, x: usize, y: usize) -> Option<(usize, usize)> {
         let empty = self.world.empty_neighbors(x, y);
         if empty.is_empty() {
             return None;
         }
 
-        let mut rng = rand::thread_rng();
-        empty.choose(&mut rng).copied()
+        empty.choose(&mut self.rng).copied()
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn handle_move_action(
         &mut self,
         id: u64,
@@ -582,12 +687,26 @@ impl SimulationState {
     ) {
         // Calculate target position
         let (dx, dy) = action.to_delta();
-        let new_x = (x as i32 + dx).max(0).min(self.world.width() as i32 - 1) as usize;
-        let new_y = (y as i32 + dy).max(0).min(self.world.height() as i32 - 1) as usize;
+        let (new_x, new_y) = self.world.step(x, y, dx as i64, dy as i64);
 
         // Check if there's a creature at the target position (before borrowing)
         let target_creature_id = self.creature_at(new_x, new_y);
 
+        // Water and rough ground cost extra to enter; walls stop the move
+        // like the edge of a bounded world, but the effort is still spent
+        let terrain = self.world.terrain(new_x, new_y);
+        let terrain_cost = match terrain {
+            Terrain::Water => config.world.terrain.water_move_cost,
+            Terrain::Rough => config.world.terrain.rough_move_cost,
+            Terrain::Plain | Terrain::Wall => 0.0,
+        };
+        // Attacking the occupant doesn't enter the cell
+        let energy_cost = if target_creature_id.is_some() {
+            energy_cost
+        } else {
+            energy_cost + terrain_cost
+        };
+
         // Try to consume energy for the move
         let has_energy = if let Some(creature) = self.creatures.get_mut(&id) {
             creature.consume_energy(energy_cost)
@@ -602,6 +721,11 @@ impl SimulationState {
                     let damage = config.combat.damage_per_attack;
                     target.metabolism.take_damage(damage);
                     target.record_damage(damage);
+                    self.events.record(SimEvent::Attack {
+                        attacker_id: id,
+                        target_id,
+                        damage,
+                    });
 
                     // Record attack direction for sensors
                     let attack_dir = match action {
@@ -613,10 +737,10 @@ impl SimulationState {
                     };
                     attacks_this_tick
                         .entry(target_id)
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(attack_dir);
                 }
-            } else {
+            } else if terrain.is_passable() {
                 // No creature, check if we can move there
                 if let Some(cell) = self.world.get(new_x, new_y) {
                     if cell.is_empty() || cell.is_food() {
@@ -628,6 +752,11 @@ impl SimulationState {
                             creature.x = new_x;
                             creature.y = new_y;
                         }
+                        self.events.record(SimEvent::Move {
+                            id,
+                            x: new_x,
+                            y: new_y,
+                        });
 
                         // Try to eat at new position
                         self.try_eat(id, config);
@@ -639,7 +768,7 @@ impl SimulationState {
 
     fn handle_attack_action(
         &mut self,
-        _id: u64,
+        id: u64,
         x: usize,
         y: usize,
         config: &Config,
@@ -647,21 +776,29 @@ impl SimulationState {
     ) {
         // Find adjacent creatures and attack them
         let adjacent = [
-            (x, y.wrapping_sub(1), Direction::Down),  // Up
-            (x, y + 1, Direction::Up),                // Down
-            (x.wrapping_sub(1), y, Direction::Right), // Left
-            (x + 1, y, Direction::Left),              // Right
+            (0, -1, Direction::Down),  // Up
+            (0, 1, Direction::Up),     // Down
+            (-1, 0, Direction::Right), // Left
+            (1, 0, Direction::Left),   // Right
         ];
 
-        for (nx, ny, dir) in adjacent {
+        for (dx, dy, dir) in adjacent {
+            let Some((nx, ny)) = self.world.offset(x, y, dx, dy) else {
+                continue;
+            };
             if let Some(target_id) = self.creature_at(nx, ny) {
                 if let Some(target) = self.creatures.get_mut(&target_id) {
                     let damage = config.combat.damage_per_strong_attack;
                     target.metabolism.take_damage(damage);
                     target.record_damage(damage);
+                    self.events.record(SimEvent::Attack {
+                        attacker_id: id,
+                        target_id,
+                        damage,
+                    });
                     attacks_this_tick
                         .entry(target_id)
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(dir);
                 }
             }
@@ -699,14 +836,17 @@ impl SimulationState {
                         config.creature.energy_cost_reproduce,
                         config.creature.initial_energy,
                         config.creature.max_energy,
-                        (
-                            config.evolution.neural_net_inputs,
-                            config.evolution.neural_net_hidden,
-                            config.evolution.neural_net_outputs,
-                        ),
                         self.tick,
+                        &mut self.rng,
                     ) {
                         parent.increment_offspring();
+                        self.events.record(SimEvent::Birth {
+                            id: offspring.id,
+                            parent_id: id,
+                            x: offspring.x,
+                            y: offspring.y,
+                            generation: offspring.genome.generation,
+                        });
                         new_creatures.push(offspring);
                         self.next_creature_id += 1;
                         self.total_births += 1;
@@ -732,11 +872,13 @@ impl SimulationState {
 
         // Find adjacent creatures
         let adjacent_positions = [
-            (x, y.wrapping_sub(1)), // Up
-            (x, y + 1),             // Down
-            (x.wrapping_sub(1), y), // Left
-            (x + 1, y),             // Right
-        ];
+            (0, -1), // Up
+            (0, 1),  // Down
+            (-1, 0), // Left
+            (1, 0),  // Right
+        ]
+        .into_iter()
+        .filter_map(|(dx, dy)| self.world.offset(x, y, dx, dy));
 
         // Find first adjacent creature to share with
         for (nx, ny) in adjacent_positions {
@@ -748,6 +890,11 @@ impl SimulationState {
                             if let Some(receiver) = self.creatures.get_mut(&receiver_id) {
                                 receiver.gain_energy(share_amount);
                             }
+                            self.events.record(SimEvent::Share {
+                                giver_id: id,
+                                receiver_id,
+                                amount: share_amount,
+                            });
                         }
                     }
                     break; // Only share with one creature
@@ -772,14 +919,7 @@ impl SimulationState {
     }
 
     pub fn count_nearby_creatures(&self, x: usize, y: usize, radius: usize) -> usize {
-        let x_min = x.saturating_sub(radius);
-        let x_max = x.saturating_add(radius).min(self.world.width() - 1);
-        let y_min = y.saturating_sub(radius);
-        let y_max = y.saturating_add(radius).min(self.world.height() - 1);
-
-        self.creature_positions
-            .iter_box(x_min, y_min, x_max, y_max)
-            .count()
+        self.creature_positions.iter_around(x, y, radius).count()
     }
 
     pub fn find_nearest_creature(&self, self_id: u64, x: usize, y: usize) -> Option<(f64, u64)> {
@@ -792,18 +932,13 @@ impl SimulationState {
         // Expand radius in steps of 5 to balance early-exit with fewer iterations
         // Sensor normalizes distance to 20 cells, so we cap at 30
         for radius in (0..=30).step_by(5) {
-            let x_min = x.saturating_sub(radius);
-            let x_max = x.saturating_add(radius).min(self.world.width() - 1);
-            let y_min = y.saturating_sub(radius);
-            let y_max = y.saturating_add(radius).min(self.world.height() - 1);
-
-            for (cx, cy, id) in self.creature_positions.iter_box(x_min, y_min, x_max, y_max) {
+            for (cx, cy, id) in self.creature_positions.iter_around(x, y, radius) {
                 if id == self_id {
                     continue;
                 }
 
-                let dx = (cx as f64 - x as f64).abs();
-                let dy = (cy as f64 - y as f64).abs();
+                let (dx, dy) = self.world.displacement((x, y), (cx, cy));
+                let (dx, dy) = (dx as f64, dy as f64);
                 // OPTIMIZATION: Compare squared distances to avoid expensive sqrt() in loop
                 // Only sqrt once at the end when returning
                 let dist_squared = dx * dx + dy * dy;
@@ -836,13 +971,8 @@ impl SimulationState {
         self_generation: u64,
         radius: usize,
     ) -> usize {
-        let x_min = x.saturating_sub(radius);
-        let x_max = x.saturating_add(radius).min(self.world.width() - 1);
-        let y_min = y.saturating_sub(radius);
-        let y_max = y.saturating_add(radius).min(self.world.height() - 1);
-
         self.creature_positions
-            .iter_box(x_min, y_min, x_max, y_max)
+            .iter_around(x, y, radius)
             .filter(|&(_, _, id)| id != self_id)
             .filter(|&(_, _, id)| {
                 self.creatures
@@ -854,14 +984,16 @@ impl SimulationState {
     }
 
     fn count_food_in_area(&self, x: usize, y: usize, radius: usize) -> usize {
-        let x_min = x.saturating_sub(radius);
-        let x_max = (x + radius).min(self.world.width() - 1);
-        let y_min = y.saturating_sub(radius);
-        let y_max = (y + radius).min(self.world.height() - 1);
+        let topology = self.world.topology();
+        let (x, y, r) = (x as i64, y as i64, radius as i64);
+        let (x_start, columns) = topology.span(x - r, x + r, self.world.width());
+        let (y_start, rows) = topology.span(y - r, y + r, self.world.height());
 
         let mut count = 0;
-        for cy in y_min..=y_max {
-            for cx in x_min..=x_max {
+        for row in 0..rows {
+            for column in 0..columns {
+                let cx = (x_start + column) % self.world.width();
+                let cy = (y_start + row) % self.world.height();
                 if let Some(cell) = self.world.get(cx, cy) {
                     if cell.is_food() {
                         count += 1;
@@ -890,6 +1022,62 @@ mod tests {
         assert_eq!(sim.tick, initial_tick + 1);
     }
 
+    #[test]
+    fn test_seeded_runs_are_identical() {
+        let mut config = Config::default();
+        config.world.width = 40;
+        config.world.height = 40;
+        config.creature.initial_population = 60;
+        config.simulation.seed = Some(1234);
+
+        let mut sim_a = SimulationState::new(&config);
+        let mut sim_b = SimulationState::new(&config);
+        for _ in 0..50 {
+            sim_a.tick(&config);
+            sim_b.tick(&config);
+        }
+
+        assert_eq!(
+            serde_json::to_value(&sim_a).unwrap(),
+            serde_json::to_value(&sim_b).unwrap()
+        );
+
+        let fresh_a = SimulationState::new(&config);
+        config.simulation.seed = Some(4321);
+        let fresh_c = SimulationState::new(&config);
+        assert_ne!(
+            serde_json::to_value(&fresh_a.world).unwrap(),
+            serde_json::to_value(&fresh_c.world).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_plant_growth_is_journaled() {
+        let mut config = Config::default();
+        config.world.width = 20;
+        config.world.height = 20;
+        config.creature.initial_population = 0;
+        config.world.plants.mode = PlantMode::Ecology;
+        config.simulation.seed = Some(7);
+
+        let mut sim = SimulationState::new(&config);
+        sim.events.set_enabled(true);
+        for _ in 0..30 {
+            let before = sim.world.total_food();
+            sim.tick(&config);
+            let spawned: u64 = sim
+                .events
+                .take()
+                .iter()
+                .map(|event| match event {
+                    SimEvent::FoodSpawn { amount, .. } => *amount as u64,
+                    _ => 0,
+                })
+                .sum();
+            assert_eq!(sim.world.total_food(), before + spawned);
+        }
+    }
+
     #[test]
     fn test_creatures_consume_energy() {
         let mut config = Config::default();
@@ -1016,7 +1204,7 @@ mod tests {
                 .count()
         }
 
-        let samples = vec![(0, 0), (10, 10), (32, 32), (63, 63)];
+        let samples = [(0, 0), (10, 10), (32, 32), (63, 63)];
         for (x, y) in samples {
             for radius in [0, 1, 5, 10, 20] {
                 let naive = naive_count(&sim, x, y, radius);
@@ -1078,7 +1266,7 @@ mod tests {
             generation: 0,
         };
 
-        let positions = vec![(5, 5), (10, 10), (15, 15)];
+        let positions = [(5, 5), (10, 10), (15, 15)];
         for (idx, (x, y)) in positions.iter().enumerate() {
             let creature = Creature::new(
                 idx as u64,
@@ -1103,4 +1291,160 @@ mod tests {
         let nearest_from_middle = sim.find_nearest_creature(1, 10, 10).unwrap();
         assert_eq!(nearest_from_middle.1, 0);
     }
+
+    #[test]
+    fn test_torus_wraps_search_movement_and_sensors() {
+        let mut config = Config::default();
+        config.world.width = 20;
+        config.world.height = 20;
+        config.world.initial_food_density = 0.0;
+        config.creature.initial_population = 0;
+
+        let place = |config: &Config| {
+            let mut sim = SimulationState::new(config);
+            let genome = Genome {
+                genes: vec![0; config.evolution.genome_size],
+                generation: 0,
+            };
+            for (id, (x, y)) in [(1, 1), (18, 18), (6, 6)].into_iter().enumerate() {
+                let creature = Creature::new(
+                    id as u64,
+                    x,
+                    y,
+                    genome.clone(),
+                    config.creature.initial_energy,
+                    config.creature.max_energy,
+                    (
+                        config.evolution.neural_net_inputs,
+                        config.evolution.neural_net_hidden,
+                        config.evolution.neural_net_outputs,
+                    ),
+                );
+                sim.add_creature_to_position(creature.id, creature.x, creature.y);
+                sim.creatures.insert(creature.id, creature);
+            }
+            sim
+        };
+
+        let bounded = place(&config);
+        assert_eq!(bounded.find_nearest_creature(0, 1, 1).unwrap().1, 2);
+
+        config.world.topology = Topology::Torus;
+        let mut sim = place(&config);
+        // (18, 18) is three cells away on each axis across the corner
+        let (dist, id) = sim.find_nearest_creature(0, 1, 1).unwrap();
+        assert_eq!(id, 1);
+        assert!((dist - 18f64.sqrt()).abs() < 1e-9);
+        assert_eq!(sim.count_nearby_creatures(0, 0, 2), 2);
+
+        let inputs = sim.get_sensor_inputs(0, 1, 1, 100.0, &config);
+        assert_eq!(&inputs[20..24], &[1.0; 4]);
+
+        let mut attacks = HashMap::new();
+        for _ in 0..2 {
+            let (x, y) = (sim.creatures[&0].x, sim.creatures[&0].y);
+            sim.handle_move_action(0, x, y, Action::MoveLeft, 0.0, &config, &mut attacks);
+        }
+        assert_eq!((sim.creatures[&0].x, sim.creatures[&0].y), (19, 1));
+        assert_eq!(sim.creature_at(19, 1), Some(0));
+        assert_eq!(sim.creature_at(1, 1), None);
+    }
+
+    #[test]
+    fn test_terrain_blocks_and_slows_movement() {
+        let mut config = Config::default();
+        config.world.width = 10;
+        config.world.height = 10;
+        config.world.initial_food_density = 0.0;
+        config.creature.initial_population = 0;
+        config.creature.energy_cost_move = 1.0;
+        config.world.terrain.water_move_cost = 2.0;
+        config.evolution.neural_net_inputs = 38;
+        config.evolution.genome_size = 401;
+
+        let mut sim = SimulationState::new(&config);
+        sim.world.set_terrain(0, 1, Terrain::Wall);
+        sim.world.set_terrain(2, 1, Terrain::Water);
+        sim.world.set_terrain(1, 0, Terrain::Rough);
+        let creature = Creature::new(
+            0,
+            1,
+            1,
+            Genome {
+                genes: vec![0; config.evolution.genome_size],
+                generation: 0,
+            },
+            100.0,
+            config.creature.max_energy,
+            (
+                config.evolution.neural_net_inputs,
+                config.evolution.neural_net_hidden,
+                config.evolution.neural_net_outputs,
+            ),
+        );
+        sim.add_creature_to_position(creature.id, creature.x, creature.y);
+        sim.creatures.insert(creature.id, creature);
+
+        let inputs = sim.get_sensor_inputs(0, 1, 1, 100.0, &config);
+        assert_eq!(&inputs[34..38], &[1.0 / 3.0, 0.0, 1.0, 2.0 / 3.0]);
+
+        let mut attacks = HashMap::new();
+        let move_cost = config.creature.energy_cost_move;
+        sim.handle_move_action(0, 1, 1, Action::MoveLeft, move_cost, &config, &mut attacks);
+        assert_eq!((sim.creatures[&0].x, sim.creatures[&0].y), (1, 1));
+        assert_eq!(sim.creatures[&0].energy(), 99.0);
+
+        sim.handle_move_action(0, 1, 1, Action::MoveRight, move_cost, &config, &mut attacks);
+        assert_eq!((sim.creatures[&0].x, sim.creatures[&0].y), (2, 1));
+        assert_eq!(sim.creatures[&0].energy(), 96.0);
+    }
+
+    #[test]
+    fn test_night_shortens_sensor_range() {
+        let mut config = Config::default();
+        config.world.width = 20;
+        config.world.height = 20;
+        config.world.initial_food_density = 0.0;
+        config.creature.initial_population = 0;
+        config.world.climate.day_length_ticks = 100;
+        config.world.climate.night_sensor_range = 0.5;
+        // Opt in to the climate sensors
+        config.evolution.neural_net_inputs = SENSOR_COUNT;
+
+        let mut sim = SimulationState::new(&config);
+        for (id, x) in [(0, 1), (1, 15)] {
+            let creature = Creature::new(
+                id,
+                x,
+                1,
+                Genome {
+                    genes: vec![0; config.evolution.genome_size],
+                    generation: 0,
+                },
+                100.0,
+                config.creature.max_energy,
+                (
+                    config.evolution.neural_net_inputs,
+                    config.evolution.neural_net_hidden,
+                    config.evolution.neural_net_outputs,
+                ),
+            );
+            sim.add_creature_to_position(creature.id, creature.x, creature.y);
+            sim.creatures.insert(creature.id, creature);
+        }
+
+        // Noon: the other creature is 14 cells away
+        sim.tick = 25;
+        let inputs = sim.get_sensor_inputs(0, 1, 1, 100.0, &config);
+        assert!((inputs[24] - 0.7).abs() < 1e-12);
+        assert_eq!(inputs[40], 0.25);
+        assert_eq!(inputs[41], 1.0);
+
+        // Midnight: sight reaches only 10 cells
+        sim.tick = 75;
+        let inputs = sim.get_sensor_inputs(0, 1, 1, 100.0, &config);
+        assert_eq!(inputs[24], 0.0);
+        assert_eq!(inputs[40], 0.75);
+        assert!(inputs[41].abs() < 1e-12);
+    }
 }
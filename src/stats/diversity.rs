@@ -0,0 +1,65 @@
+use crate::creature::Creature;
+use std::collections::HashMap;
+
+/// Genetic diversity of a population: the chance that two different
+/// creatures carry different values at a gene, averaged over gene positions.
+/// 0 for a population of clones (or fewer than two creatures), approaching 1
+/// when every creature's genes are unrelated.
+pub fn genetic_diversity<'a>(creatures: impl IntoIterator<Item = &'a Creature>) -> f64 {
+    let genomes: Vec<&[u8]> = creatures.into_iter().map(|c| c.genome.genes.as_slice()).collect();
+    let n = genomes.len();
+    let length = genomes.iter().map(|g| g.len()).min().unwrap_or(0);
+    if n < 2 || length == 0 {
+        return 0.0;
+    }
+
+    // Per position, the fraction of pairs that differ is one minus the
+    // fraction that share a value, which only needs value counts
+    let pairs = (n * (n - 1)) as f64;
+    let mut total = 0.0;
+    let mut counts: HashMap<u8, usize> = HashMap::new();
+    for position in 0..length {
+        counts.clear();
+        for genome in &genomes {
+            *counts.entry(genome[position]).or_insert(0) += 1;
+        }
+        let same: usize = counts.values().map(|&c| c * (c - 1)).sum();
+        total += 1.0 - same as f64 / pairs;
+    }
+    total / length as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::simulation::SimulationState;
+
+    #[test]
+    fn test_clones_have_no_diversity() {
+        let mut config = Config::default();
+        config.world.width = 30;
+        config.world.height = 30;
+        config.creature.initial_population = 10;
+        let mut state = SimulationState::new(&config);
+
+        let random = genetic_diversity(state.creatures.values());
+        assert!(random > 0.9, "random genomes should be diverse, got {}", random);
+
+        let genes = state.creatures.values().next().unwrap().genome.genes.clone();
+        for creature in state.creatures.values_mut() {
+            creature.genome.genes = genes.clone();
+        }
+        assert_eq!(genetic_diversity(state.creatures.values()), 0.0);
+
+        // Half the creatures differ from the rest at one gene out of `len`
+        let len = genes.len() as f64;
+        for (i, creature) in state.creatures.values_mut().enumerate() {
+            if i % 2 == 0 {
+                creature.genome.genes[0] = genes[0].wrapping_add(1);
+            }
+        }
+        let expected = (1.0 - (5.0 * 4.0 * 2.0) / (10.0 * 9.0)) / len;
+        assert!((genetic_diversity(state.creatures.values()) - expected).abs() < 1e-12);
+    }
+}
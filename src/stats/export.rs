@@ -0,0 +1,138 @@
+use super::SimulationMetrics;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// File format for exported metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricsFormat {
+    /// One row per sample with a header line
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+impl MetricsFormat {
+    /// Pick the format from a file extension: `.csv`, or `.ndjson`/`.jsonl`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(MetricsFormat::Csv),
+            "ndjson" | "jsonl" => Some(MetricsFormat::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+const CSV_COLUMNS: &[&str] = &[
+    "tick",
+    "population",
+    "total_energy",
+    "avg_energy",
+    "avg_generation",
+    "max_generation",
+    "total_food",
+    "total_births",
+    "total_deaths",
+    "avg_age",
+    "avg_digestion",
+    "herbivores",
+    "omnivores",
+    "carnivores",
+];
+
+/// Writes a series of metrics samples to a file
+pub struct MetricsWriter {
+    format: MetricsFormat,
+    writer: BufWriter<File>,
+    /// Scheduled fields and biomes get a CSV column each, fixed by the
+    /// first sample
+    extra_columns: Option<ExtraColumns>,
+}
+
+struct ExtraColumns {
+    scheduled: Vec<String>,
+    biomes: Vec<String>,
+}
+
+impl MetricsWriter {
+    pub fn create(path: &Path, format: MetricsFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        Ok(Self {
+            format,
+            writer: BufWriter::new(File::create(path)?),
+            extra_columns: None,
+        })
+    }
+
+    pub fn write(&mut self, metrics: &SimulationMetrics) -> Result<(), Box<dyn std::error::Error>> {
+        match self.format {
+            MetricsFormat::Ndjson => {
+                serde_json::to_writer(&mut self.writer, metrics)?;
+                writeln!(self.writer)?;
+            }
+            MetricsFormat::Csv => {
+                let columns = match &self.extra_columns {
+                    Some(columns) => columns,
+                    None => {
+                        let columns = ExtraColumns {
+                            scheduled: metrics.scheduled.keys().cloned().collect(),
+                            biomes: metrics.biome_food.keys().cloned().collect(),
+                        };
+                        let header: Vec<String> = CSV_COLUMNS
+                            .iter()
+                            .map(|column| column.to_string())
+                            .chain(columns.scheduled.iter().cloned())
+                            .chain(columns.biomes.iter().map(|name| format!("biome_food.{}", name)))
+                            .collect();
+                        writeln!(self.writer, "{}", header.join(","))?;
+                        self.extra_columns.insert(columns)
+                    }
+                };
+
+                let m = metrics;
+                write!(
+                    self.writer,
+                    "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                    m.tick,
+                    m.population,
+                    m.total_energy,
+                    m.avg_energy,
+                    m.avg_generation,
+                    m.max_generation,
+                    m.total_food,
+                    m.total_births,
+                    m.total_deaths,
+                    m.avg_age,
+                    m.avg_digestion,
+                    m.herbivores,
+                    m.omnivores,
+                    m.carnivores
+                )?;
+                for column in &columns.scheduled {
+                    match m.scheduled.get(column) {
+                        Some(value) => write!(self.writer, ",{}", value)?,
+                        None => write!(self.writer, ",")?,
+                    }
+                }
+                for name in &columns.biomes {
+                    match m.biome_food.get(name) {
+                        Some(food) => write!(self.writer, ",{}", food)?,
+                        None => write!(self.writer, ",")?,
+                    }
+                }
+                writeln!(self.writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::creature::Creature;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimulationMetrics {
@@ -14,6 +15,25 @@ pub struct SimulationMetrics {
     pub total_births: u64,
     pub total_deaths: u64,
     pub avg_age: f64,
+    /// Mean digestion trait, from 0 for pure plant eaters to 1 for pure
+    /// meat eaters
+    #[serde(default)]
+    pub avg_digestion: f64,
+    /// Creatures with a digestion trait below 1/3
+    #[serde(default)]
+    pub herbivores: usize,
+    /// Creatures with a digestion trait from 1/3 to 2/3
+    #[serde(default)]
+    pub omnivores: usize,
+    /// Creatures with a digestion trait above 2/3
+    #[serde(default)]
+    pub carnivores: usize,
+    /// Current value of each scheduled config field, by path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scheduled: BTreeMap<String, f64>,
+    /// Food in each biome, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub biome_food: BTreeMap<String, u64>,
 }
 
 impl SimulationMetrics {
@@ -33,6 +53,12 @@ impl SimulationMetrics {
                 total_births,
                 total_deaths,
                 avg_age: 0.0,
+                avg_digestion: 0.0,
+                herbivores: 0,
+                omnivores: 0,
+                carnivores: 0,
+                scheduled: BTreeMap::new(),
+                biome_food: BTreeMap::new(),
             };
         }
 
@@ -51,6 +77,21 @@ impl SimulationMetrics {
         let total_age: u64 = creatures.iter().map(|c| c.age).sum();
         let avg_age = total_age as f64 / population as f64;
 
+        let (mut herbivores, mut omnivores, mut carnivores) = (0, 0, 0);
+        let mut total_digestion = 0.0;
+        for creature in creatures {
+            let digestion = creature.digestion();
+            total_digestion += digestion;
+            if digestion < 1.0 / 3.0 {
+                herbivores += 1;
+            } else if digestion > 2.0 / 3.0 {
+                carnivores += 1;
+            } else {
+                omnivores += 1;
+            }
+        }
+        let avg_digestion = total_digestion / population as f64;
+
         Self {
             tick,
             population,
@@ -63,6 +104,12 @@ impl SimulationMetrics {
             total_births,
             total_deaths,
             avg_age,
+            avg_digestion,
+            herbivores,
+            omnivores,
+            carnivores,
+            scheduled: BTreeMap::new(),
+            biome_food: BTreeMap::new(),
         }
     }
 }
@@ -83,11 +130,14 @@ mod tests {
 
     #[test]
     fn test_metrics_with_creatures() {
-        let genome1 = Genome::random(100);
-        let genome2 = Genome {
+        // Gene 72 follows the 72 weights of an (8, 6, 4) brain
+        let mut genome1 = Genome::random(100, &mut rand::thread_rng());
+        genome1.genes[72] = 0;
+        let mut genome2 = Genome {
             genes: genome1.genes.clone(),
             generation: 5,
         };
+        genome2.genes[72] = 255;
 
         let c1 = Creature::new(1, 0, 0, genome1, 100.0, 200.0, (8, 6, 4));
         let c2 = Creature::new(2, 1, 1, genome2, 150.0, 200.0, (8, 6, 4));
@@ -102,5 +152,7 @@ mod tests {
         assert_eq!(metrics.max_generation, 5);
         assert_eq!(metrics.total_births, 10);
         assert_eq!(metrics.total_deaths, 5);
+        assert_eq!(metrics.avg_digestion, 0.5);
+        assert_eq!((metrics.herbivores, metrics.omnivores, metrics.carnivores), (1, 0, 1));
     }
 }
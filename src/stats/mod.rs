@@ -1,3 +1,5 @@
+pub mod diversity;
+pub mod export;
 pub mod metrics;
 
 pub use metrics::SimulationMetrics;
@@ -0,0 +1,127 @@
+use super::map::CellMap;
+use std::path::Path;
+
+/// Food settings of one biome
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fertility {
+    pub initial_food_density: f64,
+    pub food_regen_rate: f64,
+    pub max_food_per_cell: u32,
+    pub plant_decay_ticks: u32,
+}
+
+/// A biome layout read from a map file, as biome indices
+pub type BiomeMap = CellMap<u8>;
+
+impl BiomeMap {
+    /// Load a PNG image (`.png`), splitting brightness evenly between `kinds`
+    /// biomes from dark to light, or an ASCII map (anything else) of digits
+    /// naming each cell's biome
+    pub fn load(path: impl AsRef<Path>, kinds: usize) -> Result<Self, String> {
+        let kinds = kinds.clamp(1, 256);
+        Self::load_with(path.as_ref(), digit, |[r, g, b, _]| {
+            let brightness = (r as usize * 299 + g as usize * 587 + b as usize * 114) / 1000;
+            (brightness * kinds / 256) as u8
+        })
+    }
+
+    /// Parse an ASCII map of digits, one row per line. Short rows are padded
+    /// with biome 0.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::parse_with(text, digit)
+    }
+}
+
+fn digit(c: char) -> Option<u8> {
+    c.to_digit(10).map(|d| d as u8)
+}
+
+/// Split a `width`×`height` grid between `kinds` biomes in patches roughly
+/// `scale` cells across, from smooth value noise. Every biome gets an equal
+/// share of the cells.
+pub fn noise_layout(width: usize, height: usize, kinds: usize, scale: f64, seed: u64) -> Vec<u8> {
+    let kinds = kinds.clamp(1, 256);
+    let mut values = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            values.push(fractal_noise(x as f64 / scale, y as f64 / scale, seed));
+        }
+    }
+
+    // Ranking the cells rather than thresholding the raw noise keeps the
+    // biomes the same size, since noise values bunch up around the middle
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut layout = vec![0u8; values.len()];
+    for (rank, &cell) in order.iter().enumerate() {
+        layout[cell] = (rank * kinds / values.len()) as u8;
+    }
+    layout
+}
+
+/// Three octaves of value noise
+fn fractal_noise(x: f64, y: f64, seed: u64) -> f64 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    for octave in 0..3 {
+        total += amplitude * value_noise(x * frequency, y * frequency, seed.wrapping_add(octave));
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    total
+}
+
+/// Random values on the integer lattice, smoothly interpolated in between
+fn value_noise(x: f64, y: f64, seed: u64) -> f64 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+    let (ix, iy) = (x0 as i64, y0 as i64);
+
+    let top = lerp(lattice(seed, ix, iy), lattice(seed, ix + 1, iy), tx);
+    let bottom = lerp(lattice(seed, ix, iy + 1), lattice(seed, ix + 1, iy + 1), tx);
+    lerp(top, bottom, ty)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// A value in [0, 1) fixed by the seed and lattice point (SplitMix64)
+fn lattice(seed: u64, x: i64, y: i64) -> f64 {
+    let mut z = seed
+        ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_layout_is_seeded_and_balanced() {
+        let layout = noise_layout(60, 40, 3, 10.0, 7);
+        assert_eq!(layout, noise_layout(60, 40, 3, 10.0, 7));
+        assert_ne!(layout, noise_layout(60, 40, 3, 10.0, 8));
+
+        for kind in 0..3 {
+            assert_eq!(layout.iter().filter(|&&b| b == kind).count(), 800);
+        }
+
+        // Patches, not noise: most cells share a biome with the next cell
+        let same = layout.windows(2).filter(|pair| pair[0] == pair[1]).count();
+        assert!(same > layout.len() * 3 / 4, "only {} neighbours match", same);
+    }
+
+    #[test]
+    fn test_parse_biome_map() {
+        let map = BiomeMap::parse("012\n2").unwrap();
+        assert_eq!(map.stretched(3, 2), vec![0, 1, 2, 2, 0, 0]);
+        assert!(BiomeMap::parse("0a").is_err());
+    }
+}
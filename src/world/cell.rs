@@ -29,20 +29,27 @@ impl CellType {
         }
     }
 
-    pub fn add_food(&mut self, amount: u32, max: u32, is_meat: bool) {
+    /// Adds food, capped at `max`, and returns how much was actually added
+    /// and how much food of the other kind it replaced
+    pub fn add_food(&mut self, amount: u32, max: u32, is_meat: bool) -> (u32, u32) {
         match self {
             CellType::Food { amount: current, is_meat: current_is_meat, age } => {
                 // If trying to add different food type, replace it
                 if *current_is_meat != is_meat {
+                    let replaced = *current;
                     *self = CellType::Food { amount: amount.min(max), is_meat, age: 0 };
+                    (amount.min(max), replaced)
                 } else {
+                    let before = *current;
                     *current = (*current + amount).min(max);
                     // Reset age when adding more food
                     *age = 0;
+                    (current.saturating_sub(before), 0)
                 }
             }
             CellType::Empty => {
                 *self = CellType::Food { amount: amount.min(max), is_meat, age: 0 };
+                (amount.min(max), 0)
             }
         }
     }
@@ -62,7 +69,7 @@ impl CellType {
     /// Increments food age by 1 tick
     pub fn age_food(&mut self) {
         if let CellType::Food { age, .. } = self {
-            *age += 1;
+            *age = age.saturating_add(1);
         }
     }
 
@@ -122,14 +129,19 @@ mod tests {
         cell.add_food(3, 10, false);
         assert_eq!(cell.food_amount(), 8);
 
-        cell.add_food(5, 10, false);
+        assert_eq!(cell.add_food(5, 10, false), (2, 0));
         assert_eq!(cell.food_amount(), 10);
+        assert_eq!(cell.add_food(1, 10, false), (0, 0));
 
         // Test meat food
         let mut meat_cell = CellType::Empty;
         meat_cell.add_food(7, 10, true);
         assert_eq!(meat_cell.food_amount(), 7);
         assert!(meat_cell.is_meat());
+
+        // Plants replace meat
+        assert_eq!(meat_cell.add_food(1, 10, false), (1, 7));
+        assert!(!meat_cell.is_meat());
     }
 
     #[test]
@@ -0,0 +1,97 @@
+use super::{BiomeMap, TerrainMap};
+use std::path::Path;
+
+/// A grid of per-cell values read from a map file. Its size need not match
+/// the world's: it is stretched over the grid when applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellMap<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Copy + Default> CellMap<T> {
+    /// Load a PNG image (`.png`), converting each RGBA pixel with
+    /// `from_pixel`, or an ASCII map (anything else), converting each
+    /// character with `from_char`
+    pub fn load_with(
+        path: &Path,
+        from_char: impl Fn(char) -> Option<T>,
+        from_pixel: impl Fn([u8; 4]) -> T,
+    ) -> Result<Self, String> {
+        let is_image = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        if is_image {
+            let image = image::open(path)
+                .map_err(|e| format!("failed to read map {}: {}", path.display(), e))?
+                .into_rgba8();
+            let (width, height) = (image.width() as usize, image.height() as usize);
+            let cells = image.pixels().map(|pixel| from_pixel(pixel.0)).collect();
+            Ok(Self { width, height, cells })
+        } else {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read map {}: {}", path.display(), e))?;
+            Self::parse_with(&text, from_char).map_err(|e| format!("map {}: {}", path.display(), e))
+        }
+    }
+
+    /// Parse an ASCII map, one row per line. Short rows are padded with
+    /// `T::default()`.
+    pub fn parse_with(text: &str, from_char: impl Fn(char) -> Option<T>) -> Result<Self, String> {
+        let rows: Vec<&str> = text
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .collect();
+        // Trailing blank lines are usually an editor's final newline
+        let height = rows.iter().rposition(|row| !row.trim().is_empty()).map_or(0, |i| i + 1);
+        let rows = &rows[..height];
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        if width == 0 {
+            return Err("map is empty".to_string());
+        }
+
+        let mut cells = vec![T::default(); width * height];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                cells[y * width + x] = from_char(c)
+                    .ok_or_else(|| format!("unknown cell {:?} at line {}, column {}", c, y + 1, x + 1))?;
+            }
+        }
+        Ok(Self { width, height, cells })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Every value in the map, row by row
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// The map stretched (or shrunk) to a `width`×`height` grid, row by row,
+    /// each cell taking the map cell it falls in
+    pub fn stretched(&self, width: usize, height: usize) -> Vec<T> {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let my = y * self.height / height;
+            for x in 0..width {
+                let mx = x * self.width / width;
+                cells.push(self.cells[my * self.width + mx]);
+            }
+        }
+        cells
+    }
+}
+
+/// The map files a config names, read once when a world is created
+#[derive(Debug, Clone, Default)]
+pub struct WorldMaps {
+    pub terrain: Option<TerrainMap>,
+    pub biomes: Option<BiomeMap>,
+}
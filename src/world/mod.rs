@@ -1,14 +1,35 @@
+pub mod biome;
 pub mod cell;
+pub mod map;
 pub mod resources;
+pub mod terrain;
+pub mod topology;
 
 use cell::CellType;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+pub use biome::{BiomeMap, Fertility};
+pub use map::WorldMaps;
+pub use terrain::{Terrain, TerrainMap};
+pub use topology::Topology;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct World {
     width: usize,
     height: usize,
     grid: Vec<CellType>,
+    #[serde(default)]
+    topology: Topology,
+    /// Terrain of each cell, row by row. Empty when every cell is plain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    terrain: Vec<Terrain>,
+    /// Biome index of each cell, row by row. Empty when the world is one
+    /// uniform biome.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    biomes: Vec<u8>,
+    /// Name of each biome, by index
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    biome_names: Vec<String>,
 }
 
 impl World {
@@ -18,9 +39,18 @@ impl World {
             width,
             height,
             grid,
+            topology: Topology::Bounded,
+            terrain: Vec::new(),
+            biomes: Vec::new(),
+            biome_names: Vec::new(),
         }
     }
 
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -29,6 +59,119 @@ impl World {
         self.height
     }
 
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    /// The cell `(dx, dy)` away from `(x, y)`, wrapping on a torus
+    pub fn offset(&self, x: usize, y: usize, dx: i64, dy: i64) -> Option<(usize, usize)> {
+        self.topology.offset(x, y, dx, dy, self.width, self.height)
+    }
+
+    /// Where a move by `(dx, dy)` from `(x, y)` ends
+    pub fn step(&self, x: usize, y: usize, dx: i64, dy: i64) -> (usize, usize) {
+        self.topology.step(x, y, dx, dy, self.width, self.height)
+    }
+
+    /// Shortest displacement from `from` to `to`, crossing the edges on a torus
+    pub fn displacement(&self, from: (usize, usize), to: (usize, usize)) -> (i64, i64) {
+        (
+            self.topology.delta(from.0, to.0, self.width),
+            self.topology.delta(from.1, to.1, self.height),
+        )
+    }
+
+    pub fn terrain(&self, x: usize, y: usize) -> Terrain {
+        if x >= self.width || y >= self.height {
+            return Terrain::Plain;
+        }
+        self.terrain.get(y * self.width + x).copied().unwrap_or_default()
+    }
+
+    /// Terrain of every cell, row by row, or empty if every cell is plain
+    pub fn terrain_layer(&self) -> &[Terrain] {
+        &self.terrain
+    }
+
+    pub fn set_terrain(&mut self, x: usize, y: usize, terrain: Terrain) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        if self.terrain.is_empty() {
+            if terrain == Terrain::Plain {
+                return;
+            }
+            self.terrain = vec![Terrain::Plain; self.width * self.height];
+        }
+        self.terrain[y * self.width + x] = terrain;
+        if terrain == Terrain::Wall {
+            self.grid[y * self.width + x] = CellType::Empty;
+        }
+    }
+
+    /// Lay a map over the whole grid, stretched to fit
+    pub fn apply_terrain(&mut self, map: &TerrainMap) {
+        for (i, terrain) in map.stretched(self.width, self.height).into_iter().enumerate() {
+            self.set_terrain(i % self.width, i / self.width, terrain);
+        }
+    }
+
+    /// Divide the grid between biomes: `layout` holds each cell's biome
+    /// index, row by row, and `names` the name of each index
+    pub fn set_biomes(&mut self, layout: Vec<u8>, names: Vec<String>) {
+        debug_assert_eq!(layout.len(), self.width * self.height);
+        self.biomes = layout;
+        self.biome_names = names;
+    }
+
+    /// Biome index of a cell, 0 in a world without biomes
+    pub fn biome(&self, x: usize, y: usize) -> usize {
+        self.biome_at(y * self.width + x)
+    }
+
+    fn biome_at(&self, idx: usize) -> usize {
+        self.biomes.get(idx).map_or(0, |&b| b as usize)
+    }
+
+    pub fn biome_names(&self) -> &[String] {
+        &self.biome_names
+    }
+
+    /// Food settings for the cell at `idx`. Biomes without an entry in
+    /// `fertility` use the first, so it must not be empty.
+    fn fertility_at<'a>(&self, fertility: &'a [Fertility], idx: usize) -> &'a Fertility {
+        fertility.get(self.biome_at(idx)).unwrap_or(&fertility[0])
+    }
+
+    /// Total food in each biome, by index
+    pub fn food_by_biome(&self) -> Vec<u64> {
+        let mut totals = vec![0; self.biome_names.len().max(1)];
+        for (idx, cell) in self.grid.iter().enumerate() {
+            let biome = self.biome_at(idx).min(totals.len() - 1);
+            totals[biome] += cell.food_amount() as u64;
+        }
+        totals
+    }
+
+    pub fn is_passable(&self, x: usize, y: usize) -> bool {
+        self.terrain(x, y).is_passable()
+    }
+
+    /// A uniformly random cell that isn't a wall. The grid must have one.
+    pub fn random_open_cell<R: Rng + ?Sized>(&self, rng: &mut R) -> (usize, usize) {
+        loop {
+            let x = rng.gen_range(0..self.width);
+            let y = rng.gen_range(0..self.height);
+            if self.is_passable(x, y) {
+                return (x, y);
+            }
+        }
+    }
+
     pub fn get(&self, x: usize, y: usize) -> Option<&CellType> {
         if x >= self.width || y >= self.height {
             return None;
@@ -51,9 +194,7 @@ impl World {
     }
 
     pub fn neighbors(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
-        let mut result = Vec::new();
-        let x = x as i32;
-        let y = y as i32;
+        let mut result = Vec::with_capacity(8);
 
         for dx in -1..=1 {
             for dy in -1..=1 {
@@ -61,11 +202,8 @@ impl World {
                     continue;
                 }
 
-                let nx = x + dx;
-                let ny = y + dy;
-
-                if nx >= 0 && nx < self.width as i32 && ny >= 0 && ny < self.height as i32 {
-                    result.push((nx as usize, ny as usize));
+                if let Some(neighbor) = self.offset(x, y, dx, dy) {
+                    result.push(neighbor);
                 }
             }
         }
@@ -77,9 +215,10 @@ impl World {
         self.neighbors(x, y)
             .into_iter()
             .filter(|(nx, ny)| {
-                self.get(*nx, *ny)
-                    .map(|c| c.is_empty())
-                    .unwrap_or(false)
+                self.is_passable(*nx, *ny)
+                    && self.get(*nx, *ny)
+                        .map(|c| c.is_empty())
+                        .unwrap_or(false)
             })
             .collect()
     }
@@ -95,9 +234,13 @@ impl World {
             .sum()
     }
 
-    /// Ages all food by 1 tick and removes decayed food
-    pub fn age_and_decay_food(&mut self, plant_decay_ticks: u32, meat_decay_ticks: u32) {
-        for cell in self.grid.iter_mut() {
+    /// Ages all food by 1 tick and removes decayed food. Plants decay after
+    /// their biome's `plant_decay_ticks`.
+    pub fn age_and_decay_food(&mut self, fertility: &[Fertility], meat_decay_ticks: u32) {
+        for idx in 0..self.grid.len() {
+            let plant_decay_ticks = self.fertility_at(fertility, idx).plant_decay_ticks;
+            let cell = &mut self.grid[idx];
+
             // Age the food
             cell.age_food();
 
@@ -144,6 +287,19 @@ mod tests {
         assert_eq!(neighbors.len(), 3);
     }
 
+    #[test]
+    fn test_torus_neighbors_wrap() {
+        let world = World::new(10, 10).with_topology(Topology::Torus);
+
+        let neighbors = world.neighbors(0, 0);
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.contains(&(9, 9)));
+        assert!(neighbors.contains(&(0, 9)));
+        assert!(neighbors.contains(&(9, 1)));
+
+        assert_eq!(world.displacement((9, 0), (0, 9)), (1, -1));
+    }
+
     #[test]
     fn test_world_empty_neighbors() {
         let mut world = World::new(10, 10);
@@ -154,6 +310,31 @@ mod tests {
         assert_eq!(empty.len(), 6);
     }
 
+    #[test]
+    fn test_terrain_layer() {
+        let mut world = World::new(4, 2);
+        world.set_terrain(0, 0, Terrain::Plain);
+        assert!(world.terrain_layer().is_empty());
+
+        world.set(1, 0, CellType::Food { amount: 5, is_meat: false, age: 0 });
+        world.apply_terrain(&TerrainMap::parse(".#\n~^").unwrap());
+        assert_eq!(world.terrain(1, 0), Terrain::Plain);
+        assert_eq!(world.terrain(2, 0), Terrain::Wall);
+        assert_eq!(world.terrain(3, 1), Terrain::Rough);
+        assert!(!world.is_passable(3, 0));
+
+        // Walls never hold food or count as free space
+        world.set_terrain(1, 0, Terrain::Wall);
+        assert!(world.get(1, 0).unwrap().is_empty());
+        assert_eq!(world.empty_neighbors(0, 0), vec![(0, 1), (1, 1)]);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let (x, y) = world.random_open_cell(&mut rng);
+            assert!(world.is_passable(x, y));
+        }
+    }
+
     #[test]
     fn test_world_total_food() {
         let mut world = World::new(10, 10);
@@ -1,14 +1,31 @@
-use super::World;
+use super::{CellType, Fertility, World};
+use rand::seq::SliceRandom;
 use rand::Rng;
 
-impl World {
-    pub fn initialize_food(&mut self, density: f64, max_per_cell: u32) {
-        let mut rng = rand::thread_rng();
+/// Food added to one cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoodDrop {
+    pub x: usize,
+    pub y: usize,
+    /// Units actually added, after the cell's cap
+    pub amount: u32,
+    /// Units of the other kind of food the drop replaced
+    pub replaced: u32,
+}
 
+impl World {
+    /// Scatter plant food over the grid, each cell with its biome's density
+    /// and cap
+    pub fn initialize_food<R: Rng + ?Sized>(&mut self, fertility: &[Fertility], rng: &mut R) {
         for y in 0..self.height() {
             for x in 0..self.width() {
+                let biome = self.fertility_at(fertility, y * self.width() + x);
+                let (density, max_per_cell) = (biome.initial_food_density, biome.max_food_per_cell);
                 if rng.gen::<f64>() < density {
                     let amount = rng.gen_range(1..=max_per_cell);
+                    if !self.is_passable(x, y) {
+                        continue;
+                    }
                     if let Some(cell) = self.get_mut(x, y) {
                         cell.add_food(amount, max_per_cell, false); // Plant food
                     }
@@ -17,21 +34,93 @@ impl World {
         }
     }
 
-    pub fn regenerate_food(&mut self, rate: f64, max_per_cell: u32) {
+    /// Drops one unit of plant food on randomly sampled cells, skipping walls,
+    /// and returns what changed. Cells already full are left out. Each biome
+    /// regrows at its own rate.
+    pub fn regenerate_food<R: Rng + ?Sized>(&mut self, fertility: &[Fertility], rng: &mut R) -> Vec<FoodDrop> {
         // OPTIMIZATION: Instead of checking all cells (width * height iterations),
         // calculate expected number of cells to regenerate and randomly sample them.
         // At 300x300 with rate=0.001: checks ~90 cells instead of 90,000 (1000x faster)
+        // Sampling at the fastest biome's rate and keeping each sample with
+        // probability rate / fastest gives every biome its own rate.
+        let fastest = fertility.iter().map(|f| f.food_regen_rate).fold(0.0, f64::max);
         let total_cells = self.width() * self.height();
-        let num_cells_to_regen = ((total_cells as f64) * rate).round() as usize;
+        let num_cells_to_regen = ((total_cells as f64) * fastest).round() as usize;
 
-        let mut rng = rand::thread_rng();
+        let mut spawned = Vec::with_capacity(num_cells_to_regen);
         for _ in 0..num_cells_to_regen {
             let x = rng.gen_range(0..self.width());
             let y = rng.gen_range(0..self.height());
+            let biome = *self.fertility_at(fertility, y * self.width() + x);
+            if biome.food_regen_rate < fastest && rng.gen::<f64>() * fastest >= biome.food_regen_rate {
+                continue;
+            }
+            if !self.is_passable(x, y) {
+                continue;
+            }
             if let Some(cell) = self.get_mut(x, y) {
-                cell.add_food(1, max_per_cell, false); // Plant food regeneration
+                let (amount, replaced) = cell.add_food(1, biome.max_food_per_cell, false); // Plant food regeneration
+                if amount > 0 || replaced > 0 {
+                    spawned.push(FoodDrop { x, y, amount, replaced });
+                }
             }
         }
+        spawned
+    }
+
+    /// Grows every plant logistically towards its biome's `max_food_per_cell`
+    /// and lets plants seed empty neighbouring cells, more often the fuller
+    /// they are. Returns what changed, growth and sprouted seeds alike. A
+    /// plant eaten down to nothing is gone until a neighbour seeds its cell
+    /// again.
+    pub fn grow_plants<R: Rng + ?Sized>(
+        &mut self,
+        growth_rate: f64,
+        seed_rate: f64,
+        fertility: &[Fertility],
+        rng: &mut R,
+    ) -> Vec<FoodDrop> {
+        let mut grown = Vec::new();
+        let mut seeds = Vec::new();
+        for idx in 0..self.grid.len() {
+            let CellType::Food { amount, is_meat: false, .. } = self.grid[idx] else {
+                continue;
+            };
+            let capacity = self.fertility_at(fertility, idx).max_food_per_cell;
+            let fullness = (amount as f64 / capacity as f64).min(1.0);
+
+            // Growth comes in whole units, so the fractional part is a chance
+            // of one more
+            let growth = growth_rate * amount as f64 * (1.0 - fullness);
+            let units = growth.floor() as u32 + u32::from(rng.gen::<f64>() < growth.fract());
+            let (x, y) = (idx % self.width(), idx / self.width());
+            if units > 0 {
+                let (amount, _) = self.grid[idx].add_food(units, capacity, false);
+                if amount > 0 {
+                    grown.push(FoodDrop { x, y, amount, replaced: 0 });
+                }
+            }
+
+            if rng.gen::<f64>() < seed_rate * fullness {
+                if let Some(&target) = self.neighbors(x, y).choose(rng) {
+                    seeds.push(target);
+                }
+            }
+        }
+
+        // Seeds sprout after the pass so they don't grow in the tick they
+        // land. Only empty ground takes a seed.
+        for (x, y) in seeds {
+            if !self.is_passable(x, y) {
+                continue;
+            }
+            let capacity = self.fertility_at(fertility, y * self.width() + x).max_food_per_cell;
+            if let Some(cell) = self.get_mut(x, y).filter(|cell| cell.is_empty()) {
+                let (amount, _) = cell.add_food(1, capacity, false);
+                grown.push(FoodDrop { x, y, amount, replaced: 0 });
+            }
+        }
+        grown
     }
 }
 
@@ -39,10 +128,19 @@ impl World {
 mod tests {
     use super::*;
 
+    fn uniform(density: f64, rate: f64, max_per_cell: u32) -> [Fertility; 1] {
+        [Fertility {
+            initial_food_density: density,
+            food_regen_rate: rate,
+            max_food_per_cell: max_per_cell,
+            plant_decay_ticks: 600,
+        }]
+    }
+
     #[test]
     fn test_initialize_food() {
         let mut world = World::new(100, 100);
-        world.initialize_food(0.3, 10);
+        world.initialize_food(&uniform(0.3, 0.0, 10), &mut rand::thread_rng());
 
         let food_count = world.count_cells(|c| c.is_food());
         assert!(food_count > 0);
@@ -55,12 +153,13 @@ mod tests {
     #[test]
     fn test_regenerate_food() {
         let mut world = World::new(100, 100);
-        world.initialize_food(0.1, 10);
+        world.initialize_food(&uniform(0.1, 0.0, 10), &mut rand::thread_rng());
 
         let initial_food = world.total_food();
 
+        let mut rng = rand::thread_rng();
         for _ in 0..100 {
-            world.regenerate_food(0.01, 10);
+            world.regenerate_food(&uniform(0.0, 0.01, 10), &mut rng);
         }
 
         let final_food = world.total_food();
@@ -71,8 +170,9 @@ mod tests {
     fn test_food_cap() {
         let mut world = World::new(10, 10);
 
+        let mut rng = rand::thread_rng();
         for _ in 0..1000 {
-            world.regenerate_food(1.0, 5);
+            world.regenerate_food(&uniform(0.0, 1.0, 5), &mut rng);
         }
 
         for y in 0..10 {
@@ -83,4 +183,63 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_regenerate_reports_what_changed() {
+        let mut world = World::new(2, 1);
+        world.set(0, 0, CellType::Food { amount: 5, is_meat: false, age: 0 });
+        world.set(1, 0, CellType::Food { amount: 3, is_meat: true, age: 0 });
+
+        let mut rng = rand::thread_rng();
+        let drops: Vec<FoodDrop> = (0..20)
+            .flat_map(|_| world.regenerate_food(&uniform(0.0, 1.0, 5), &mut rng))
+            .collect();
+        // The full cell takes nothing, and the meat is replaced once
+        assert!(drops.iter().all(|drop| (drop.x, drop.y) == (1, 0)));
+        assert_eq!(drops.iter().map(|drop| drop.replaced).collect::<Vec<_>>()[..1], [3]);
+        assert_eq!(drops.iter().map(|drop| drop.amount).sum::<u32>(), world.get(1, 0).unwrap().food_amount());
+    }
+
+    #[test]
+    fn test_biomes_regrow_at_their_own_rate() {
+        let mut world = World::new(20, 10);
+        // Left half barren, right half fertile but capped at 2 per cell
+        let layout = (0..200).map(|i| u8::from(i % 20 >= 10)).collect();
+        world.set_biomes(layout, vec!["desert".to_string(), "meadow".to_string()]);
+        let [desert] = uniform(0.0, 0.0, 10);
+        let [meadow] = uniform(1.0, 0.2, 2);
+        let fertility = [desert, meadow];
+
+        let mut rng = rand::thread_rng();
+        world.initialize_food(&fertility, &mut rng);
+        for _ in 0..100 {
+            world.regenerate_food(&fertility, &mut rng);
+        }
+
+        let totals = world.food_by_biome();
+        assert_eq!(totals, vec![0, 200]);
+        assert_eq!(world.biome(15, 3), 1);
+    }
+
+    #[test]
+    fn test_plants_grow_spread_and_die_out() {
+        let mut world = World::new(10, 10);
+        world.set(5, 5, CellType::Food { amount: 1, is_meat: false, age: 0 });
+        let fertility = uniform(0.0, 0.0, 10);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            world.grow_plants(0.5, 0.5, &fertility, &mut rng);
+        }
+        assert_eq!(world.get(5, 5).unwrap().food_amount(), 10);
+        assert!(world.count_cells(|c| c.is_food()) > 9);
+        assert_eq!(world.count_cells(|c| c.food_amount() > 10), 0);
+
+        // Grazed bare, nothing is left to regrow or seed
+        let mut bare = World::new(10, 10);
+        for _ in 0..100 {
+            assert!(bare.grow_plants(0.5, 0.5, &fertility, &mut rng).is_empty());
+        }
+        assert_eq!(bare.total_food(), 0);
+    }
 }
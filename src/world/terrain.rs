@@ -0,0 +1,159 @@
+use super::map::CellMap;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Ground a cell is made of, independent of any food lying on it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum Terrain {
+    #[default]
+    Plain,
+    /// Impassable. Nothing spawns, moves or grows here.
+    Wall,
+    /// Passable for `world.terrain.water_move_cost` extra energy
+    Water,
+    /// Passable for `world.terrain.rough_move_cost` extra energy
+    Rough,
+}
+
+impl Terrain {
+    pub fn is_passable(self) -> bool {
+        self != Terrain::Wall
+    }
+
+    /// Sensor reading, from 0 for plain ground up to 1 for a wall
+    pub fn sensor_value(self) -> f64 {
+        match self {
+            Terrain::Plain => 0.0,
+            Terrain::Rough => 1.0 / 3.0,
+            Terrain::Water => 2.0 / 3.0,
+            Terrain::Wall => 1.0,
+        }
+    }
+
+    /// Terrain for a character of an ASCII map
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' | ' ' => Some(Terrain::Plain),
+            '#' => Some(Terrain::Wall),
+            '~' => Some(Terrain::Water),
+            '^' => Some(Terrain::Rough),
+            _ => None,
+        }
+    }
+
+    /// Terrain for a pixel of an image map: whichever of white (plain),
+    /// black (wall), blue (water) or grey (rough) the colour is closest to
+    fn from_rgb([r, g, b]: [u8; 3]) -> Self {
+        const PALETTE: [(Terrain, [i32; 3]); 4] = [
+            (Terrain::Plain, [255, 255, 255]),
+            (Terrain::Wall, [0, 0, 0]),
+            (Terrain::Water, [0, 0, 255]),
+            (Terrain::Rough, [128, 128, 128]),
+        ];
+        let distance = |[pr, pg, pb]: [i32; 3]| {
+            let (dr, dg, db) = (r as i32 - pr, g as i32 - pg, b as i32 - pb);
+            dr * dr + dg * dg + db * db
+        };
+        PALETTE
+            .iter()
+            .min_by_key(|(_, colour)| distance(*colour))
+            .map(|(terrain, _)| *terrain)
+            .unwrap_or_default()
+    }
+}
+
+impl From<Terrain> for u8 {
+    fn from(terrain: Terrain) -> Self {
+        match terrain {
+            Terrain::Plain => 0,
+            Terrain::Wall => 1,
+            Terrain::Water => 2,
+            Terrain::Rough => 3,
+        }
+    }
+}
+
+impl TryFrom<u8> for Terrain {
+    type Error = String;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        match code {
+            0 => Ok(Terrain::Plain),
+            1 => Ok(Terrain::Wall),
+            2 => Ok(Terrain::Water),
+            3 => Ok(Terrain::Rough),
+            _ => Err(format!("unknown terrain code {}", code)),
+        }
+    }
+}
+
+/// A terrain layout read from a map file
+pub type TerrainMap = CellMap<Terrain>;
+
+impl TerrainMap {
+    /// Load a PNG image (`.png`) or an ASCII map (anything else)
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        Self::load_with(path.as_ref(), Terrain::from_char, |[r, g, b, a]| {
+            // Transparent pixels are open ground whatever their colour
+            if a < 128 {
+                Terrain::Plain
+            } else {
+                Terrain::from_rgb([r, g, b])
+            }
+        })
+    }
+
+    /// Parse an ASCII map, one row per line: `.` or space for plain ground,
+    /// `#` wall, `~` water, `^` rough ground. Short rows are padded with
+    /// plain ground.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::parse_with(text, Terrain::from_char)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ascii_map() {
+        let map = TerrainMap::parse("#~^\n. #\r\n#\n\n").unwrap();
+        assert_eq!((map.width(), map.height()), (3, 3));
+        assert_eq!(
+            map.stretched(3, 3),
+            vec![
+                Terrain::Wall, Terrain::Water, Terrain::Rough,
+                Terrain::Plain, Terrain::Plain, Terrain::Wall,
+                Terrain::Wall, Terrain::Plain, Terrain::Plain,
+            ]
+        );
+
+        // Each map cell covers a 2x2 block of a world twice the size
+        let stretched = map.stretched(6, 6);
+        assert_eq!(stretched[0..6], [Terrain::Wall, Terrain::Wall, Terrain::Water, Terrain::Water, Terrain::Rough, Terrain::Rough]);
+        assert_eq!(stretched[6..12], stretched[0..6]);
+
+        let error = TerrainMap::parse("..\n.x").unwrap_err();
+        assert!(error.contains("'x' at line 2, column 2"), "{}", error);
+        assert!(TerrainMap::parse("\n\n").is_err());
+    }
+
+    #[test]
+    fn test_load_image_map() {
+        let path = std::env::temp_dir().join(format!("evo_terrain_{}.png", std::process::id()));
+        let mut image = image::RgbaImage::new(2, 2);
+        image.put_pixel(0, 0, image::Rgba([250, 250, 240, 255]));
+        image.put_pixel(1, 0, image::Rgba([10, 10, 10, 255]));
+        image.put_pixel(0, 1, image::Rgba([30, 60, 220, 255]));
+        image.put_pixel(1, 1, image::Rgba([0, 0, 0, 0]));
+        image.save(&path).unwrap();
+
+        let map = TerrainMap::load(&path);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(
+            map.unwrap().stretched(2, 2),
+            vec![Terrain::Plain, Terrain::Wall, Terrain::Water, Terrain::Plain]
+        );
+    }
+}
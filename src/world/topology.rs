@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+/// How the edges of the grid behave
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    /// The grid ends at its edges: moves stop at the border and cells past it
+    /// don't exist
+    #[default]
+    Bounded,
+    /// Opposite edges are joined, so leaving one side enters the other and
+    /// every cell has a full neighbourhood
+    Torus,
+}
+
+impl Topology {
+    /// The cell `(dx, dy)` away from `(x, y)` on a `width`×`height` grid, or
+    /// None if that lies past the edge of a bounded grid
+    pub fn offset(
+        self,
+        x: usize,
+        y: usize,
+        dx: i64,
+        dy: i64,
+        width: usize,
+        height: usize,
+    ) -> Option<(usize, usize)> {
+        let nx = x as i64 + dx;
+        let ny = y as i64 + dy;
+        match self {
+            Topology::Bounded => {
+                if nx >= 0 && nx < width as i64 && ny >= 0 && ny < height as i64 {
+                    Some((nx as usize, ny as usize))
+                } else {
+                    None
+                }
+            }
+            Topology::Torus => Some((
+                nx.rem_euclid(width as i64) as usize,
+                ny.rem_euclid(height as i64) as usize,
+            )),
+        }
+    }
+
+    /// Where a move by `(dx, dy)` from `(x, y)` ends: stopped at the border of
+    /// a bounded grid, wrapped around a torus
+    pub fn step(self, x: usize, y: usize, dx: i64, dy: i64, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Topology::Bounded => (
+                (x as i64 + dx).clamp(0, width as i64 - 1) as usize,
+                (y as i64 + dy).clamp(0, height as i64 - 1) as usize,
+            ),
+            Topology::Torus => self
+                .offset(x, y, dx, dy, width, height)
+                .expect("torus offsets always land on the grid"),
+        }
+    }
+
+    /// Shortest signed displacement from `from` to `to` along an axis of
+    /// `size` cells. On a torus this may cross the edge.
+    pub fn delta(self, from: usize, to: usize, size: usize) -> i64 {
+        let d = to as i64 - from as i64;
+        match self {
+            Topology::Bounded => d,
+            Topology::Torus => {
+                let size = size as i64;
+                let d = d.rem_euclid(size);
+                if d > size / 2 {
+                    d - size
+                } else {
+                    d
+                }
+            }
+        }
+    }
+
+    /// The cells of the inclusive range `min..=max` along an axis of `size`
+    /// cells, as a first cell and a count. A bounded grid clips the range; a
+    /// torus wraps it and covers each cell at most once.
+    pub fn span(self, min: i64, max: i64, size: usize) -> (usize, usize) {
+        if max < min || size == 0 {
+            return (0, 0);
+        }
+        match self {
+            Topology::Bounded => {
+                let first = min.max(0);
+                let last = max.min(size as i64 - 1);
+                if first > last {
+                    (0, 0)
+                } else {
+                    (first as usize, (last - first + 1) as usize)
+                }
+            }
+            Topology::Torus => {
+                let count = ((max - min + 1) as u64).min(size as u64) as usize;
+                (min.rem_euclid(size as i64) as usize, count)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_torus_wraps_offsets_and_distances() {
+        assert_eq!(Topology::Bounded.offset(0, 5, -1, 0, 10, 10), None);
+        assert_eq!(Topology::Torus.offset(0, 5, -1, 0, 10, 10), Some((9, 5)));
+        assert_eq!(Topology::Torus.offset(9, 9, 1, 1, 10, 10), Some((0, 0)));
+
+        assert_eq!(Topology::Bounded.step(0, 0, -1, -1, 10, 10), (0, 0));
+        assert_eq!(Topology::Torus.step(0, 0, -1, -1, 10, 10), (9, 9));
+
+        assert_eq!(Topology::Bounded.delta(1, 9, 10), 8);
+        assert_eq!(Topology::Torus.delta(1, 9, 10), -2);
+        assert_eq!(Topology::Torus.delta(9, 1, 10), 2);
+
+        assert_eq!(Topology::Bounded.span(-2, 2, 10), (0, 3));
+        assert_eq!(Topology::Bounded.span(8, 12, 10), (8, 2));
+        assert_eq!(Topology::Torus.span(-2, 2, 10), (8, 5));
+        assert_eq!(Topology::Torus.span(-30, 30, 10), (0, 10));
+    }
+}
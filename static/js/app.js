@@ -9,6 +9,7 @@ const AppState = {
     // Simulation state
     worldWidth: 0,
     worldHeight: 0,
+    terrain: [], // Row-major terrain codes, empty when every cell is plain
     creatures: [],
     food: [],
     metrics: {},
@@ -27,7 +28,10 @@ const AppState = {
 // WebSocket connection
 function connectWebSocket() {
     const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-    const wsUrl = `${protocol}//${window.location.host}/ws`;
+    // ?world=<id> views another world hosted by the same server
+    const world = new URLSearchParams(window.location.search).get('world');
+    const path = world ? `/ws/${encodeURIComponent(world)}` : '/ws';
+    const wsUrl = `${protocol}//${window.location.host}${path}`;
 
     updateConnectionStatus('connecting');
     console.log(`Connecting to WebSocket: ${wsUrl}`);
@@ -186,6 +190,7 @@ function handleFullState(message) {
     // Extract world dimensions (sent directly in message, not nested in world object)
     AppState.worldWidth = message.world_width || 0;
     AppState.worldHeight = message.world_height || 0;
+    AppState.terrain = message.terrain || [];
 
     console.log(`World size: ${AppState.worldWidth}x${AppState.worldHeight}`);
     console.log(`Creatures: ${AppState.creatures.length}`);
@@ -229,6 +229,11 @@ function renderWorld(appState) {
     // Draw grid
     drawGrid(ctx);
 
+    // Draw terrain
+    if (appState.terrain && appState.terrain.length > 0) {
+        drawTerrain(ctx, appState.terrain);
+    }
+
     // Draw food
     if (appState.food && appState.food.length > 0) {
         drawFood(ctx, appState.food);
@@ -274,6 +279,20 @@ function drawGrid(ctx) {
     ctx.strokeRect(0, 0, width * cellSize, height * cellSize);
 }
 
+// Draw terrain: walls, water and rough ground
+function drawTerrain(ctx, terrain) {
+    const cellSize = RendererState.cellSize;
+    const width = RendererState.worldWidth;
+    const colors = [null, '#5a5a5a', '#1e3a6e', '#3b3323']; // plain, wall, water, rough
+
+    terrain.forEach((code, i) => {
+        const color = colors[code];
+        if (!color) return;
+        ctx.fillStyle = color;
+        ctx.fillRect((i % width) * cellSize, Math.floor(i / width) * cellSize, cellSize, cellSize);
+    });
+}
+
 // Draw food
 function drawFood(ctx, food) {
     const cellSize = RendererState.cellSize;
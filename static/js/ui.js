@@ -24,6 +24,11 @@ function updateStatistics(metrics) {
     setStatValue('stat-deaths', metrics.total_deaths || 0);
     setStatValue('stat-avg-age', formatNumber(metrics.avg_age || 0, 1));
 
+    // Diet
+    setStatValue('stat-herbivores', metrics.herbivores || 0);
+    setStatValue('stat-omnivores', metrics.omnivores || 0);
+    setStatValue('stat-carnivores', metrics.carnivores || 0);
+
     // World
     const worldSize = window.AppState ?
         `${window.AppState.worldWidth} × ${window.AppState.worldHeight}` : '-';
@@ -262,7 +267,15 @@ function renderSensors(container, sensors) {
         'Nearest Creature Health',   // 26
         'Kin Density (5×5)',         // 27
         'Food Density (5×5)',        // 28
-        'Crowding (3×3)'             // 29
+        'Crowding (3×3)',            // 29
+        'Food Up',                   // 30
+        'Food Down',                 // 31
+        'Food Left',                 // 32
+        'Food Right',                // 33
+        'Terrain Up',                // 34
+        'Terrain Down',              // 35
+        'Terrain Left',              // 36
+        'Terrain Right'              // 37
     ];
 
     container.innerHTML = '';